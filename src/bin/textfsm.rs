@@ -0,0 +1,525 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::ExitCode;
+use textfsm_rs::cli_table::ParsedCliTable;
+use textfsm_rs::encoding::{self, Encoding};
+use textfsm_rs::metadata::Metadata;
+use textfsm_rs::output::{self, CsvOptions, JsonShape};
+use textfsm_rs::preprocess::Preprocessor;
+use textfsm_rs::schema::SchemaOptions;
+use textfsm_rs::template_fmt::format_template;
+use textfsm_rs::*;
+
+/// Results differ from the expected ones, or validation found errors
+const EXIT_FAILURE: u8 = 1;
+/// A template or an index does not load or compile
+const EXIT_TEMPLATE_ERROR: u8 = 3;
+/// The template reached the Error state
+const EXIT_PARSE_ERROR: u8 = 4;
+
+#[derive(Parser)]
+#[command(
+    name = "textfsm",
+    version,
+    about = "Parse semi-structured text with TextFSM templates"
+)]
+struct Cli {
+    /// Output format of the parse results
+    #[arg(long, short, global = true, value_enum, default_value_t = Format::Table)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Yaml,
+    Csv,
+    Table,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InputEncoding {
+    /// UTF-8, the invalid sequences become U+FFFD
+    Utf8Lossy,
+    /// UTF-8, the invalid sequences are read as Latin-1
+    Utf8Latin1,
+    Latin1,
+}
+
+impl From<InputEncoding> for Encoding {
+    fn from(e: InputEncoding) -> Self {
+        match e {
+            InputEncoding::Utf8Lossy => Encoding::Utf8Lossy,
+            InputEncoding::Utf8Latin1 => Encoding::Utf8OrLatin1,
+            InputEncoding::Latin1 => Encoding::Latin1,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Parse the input with a template
+    Parse {
+        template: String,
+        /// Input file, standard input if absent or "-"
+        input: Option<String>,
+        /// Go on after the Error transitions, and report them and the unmatched
+        /// lines on standard error
+        #[arg(long)]
+        lenient: bool,
+        /// Decode input that is not valid UTF-8, instead of failing
+        #[arg(long, value_enum)]
+        encoding: Option<InputEncoding>,
+        /// Strip the ANSI escapes, the pager prompts, the backspace and CR
+        /// overwrites and the trailing whitespace of a session capture
+        #[arg(long)]
+        clean: bool,
+    },
+    /// Parse the output of a command with the template from the index
+    ParseCmd {
+        /// Index file, the earlier ones take precedence
+        #[arg(long = "index", required = true)]
+        indexes: Vec<String>,
+        #[arg(long)]
+        platform: String,
+        #[arg(long)]
+        command: String,
+        /// Other index attribute, like Hostname=router1
+        #[arg(long = "attr", value_parser = parse_attribute)]
+        attributes: Vec<(String, String)>,
+        /// Column added to every record, like HOSTNAME=router1
+        #[arg(long = "meta", value_parser = parse_attribute)]
+        metadata: Vec<(String, String)>,
+        /// Input file, standard input if absent or "-"
+        input: Option<String>,
+    },
+    /// Rank the templates of the index by how well they fit the input
+    Detect {
+        /// Index file, the earlier ones take precedence
+        #[arg(long = "index", required = true)]
+        indexes: Vec<String>,
+        /// Only try the templates of the platform
+        #[arg(long)]
+        platform: Option<String>,
+        /// Input file, standard input if absent or "-"
+        input: Option<String>,
+    },
+    /// Check that index files and templates load and compile
+    Validate {
+        #[arg(long = "index")]
+        indexes: Vec<String>,
+        templates: Vec<String>,
+    },
+    /// Reformat a template in the canonical layout
+    Fmt {
+        template: String,
+        /// Do not print, fail if the template is not formatted
+        #[arg(long, conflicts_with = "write")]
+        check: bool,
+        /// Overwrite the template with the formatted one
+        #[arg(long, short)]
+        write: bool,
+    },
+    /// Parse the input and compare the results with the parsed_sample of a YAML file
+    Test {
+        template: String,
+        input: String,
+        expected: String,
+    },
+    /// Print the state machine of a template as a Graphviz digraph
+    Dot { template: String },
+    /// Print the JSON Schema of the records of a template
+    Schema {
+        template: String,
+        /// Use the types of the "# @type" directives
+        #[arg(long)]
+        typed: bool,
+    },
+}
+
+struct CliError {
+    code: u8,
+    message: String,
+}
+
+impl CliError {
+    fn new(code: u8, message: impl Into<String>) -> Self {
+        CliError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+fn parse_attribute(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((k, v)) => Ok((k.to_string(), v.to_string())),
+        None => Err(format!("expected NAME=VALUE, got '{}'", s)),
+    }
+}
+
+fn read_input(input: &Option<String>) -> Result<String, CliError> {
+    match input.as_deref() {
+        None | Some("-") => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| CliError::new(EXIT_FAILURE, format!("stdin: {}", e)))?;
+            Ok(text)
+        }
+        Some(fname) => std::fs::read_to_string(fname)
+            .map_err(|e| CliError::new(EXIT_FAILURE, format!("{}: {}", fname, e))),
+    }
+}
+
+/// Read the input with an encoding, reporting the invalid UTF-8 on standard error.
+fn read_input_decoded(input: &Option<String>, encoding: Encoding) -> Result<String, CliError> {
+    let (name, bytes) = match input.as_deref() {
+        None | Some("-") => {
+            let mut bytes = vec![];
+            std::io::stdin()
+                .read_to_end(&mut bytes)
+                .map_err(|e| CliError::new(EXIT_FAILURE, format!("stdin: {}", e)))?;
+            ("stdin", bytes)
+        }
+        Some(fname) => {
+            let bytes = std::fs::read(fname)
+                .map_err(|e| CliError::new(EXIT_FAILURE, format!("{}: {}", fname, e)))?;
+            (fname, bytes)
+        }
+    };
+    let decoded = encoding::decode(&bytes, encoding);
+    for invalid in &decoded.invalid_sequences {
+        eprintln!(
+            "textfsm: {}: line {}: invalid UTF-8 {:02x?} at byte {}",
+            name, invalid.line, invalid.bytes, invalid.offset
+        );
+    }
+    Ok(decoded.text)
+}
+
+fn load_template(fname: &str) -> Result<TextFSMParser, CliError> {
+    let template = std::fs::read_to_string(fname)
+        .map_err(|e| CliError::new(EXIT_TEMPLATE_ERROR, format!("{}: {}", fname, e)))?;
+    TextFSMParser::from_string(&template)
+        .map_err(|e| CliError::new(EXIT_TEMPLATE_ERROR, format!("{}: {}", fname, e)))
+}
+
+fn parse_error(e: TextFSMError) -> CliError {
+    CliError::new(EXIT_PARSE_ERROR, e.to_string())
+}
+
+fn write_output(out: &ParsedOutput, format: Format) -> std::io::Result<()> {
+    let stdout = std::io::stdout();
+    let mut w = stdout.lock();
+    match format {
+        Format::Json => output::write_json(w, &out.header, &out.records, JsonShape::Objects),
+        Format::Yaml => output::write_parsed_sample_yaml(w, &out.header, &out.records),
+        Format::Csv => output::write_csv(w, &out.header, &out.records, &CsvOptions::default()),
+        Format::Table => write!(w, "{}", out.to_text_table(TextTable::terminal_width())),
+    }
+}
+
+fn output(out: &ParsedOutput, format: Format) -> Result<(), CliError> {
+    write_output(out, format).map_err(|e| CliError::new(EXIT_FAILURE, format!("output: {}", e)))
+}
+
+fn run_parse(
+    template: &str,
+    input: &Option<String>,
+    lenient: bool,
+    encoding: Option<InputEncoding>,
+    clean: bool,
+    format: Format,
+) -> Result<(), CliError> {
+    let parser = load_template(template)?;
+    let text = match encoding {
+        None => read_input(input)?,
+        Some(encoding) => read_input_decoded(input, encoding.into())?,
+    };
+    let mut textfsm = TextFSM::from_parser(parser);
+    if clean {
+        textfsm = textfsm.with_preprocessor(Preprocessor::cli_session());
+    }
+    let records = if lenient {
+        let out = textfsm.parse_string_lenient(&text, None);
+        for d in &out.diagnostics {
+            let message = d.message.as_deref().unwrap_or("Error state reached");
+            eprintln!(
+                "textfsm: line {}: state {}: {}",
+                d.line_number, d.state, message
+            );
+        }
+        for u in &out.unmatched_lines {
+            eprintln!(
+                "textfsm: line {}: state {}: unmatched '{}'",
+                u.line_number, u.state, u.line
+            );
+        }
+        if let Some(e) = out.error {
+            return Err(parse_error(e));
+        }
+        out.records
+    } else {
+        textfsm.try_parse_string(&text, None).map_err(parse_error)?
+    };
+    let out = ParsedOutput {
+        header: textfsm.header(),
+        records,
+    };
+    output(&out, format)
+}
+
+fn load_indexes(indexes: &[String]) -> Result<CliTable, CliError> {
+    let mut tables: Vec<ParsedCliTable> = vec![];
+    for fname in indexes {
+        let table = ParsedCliTable::try_from_file(fname)
+            .map_err(|e| CliError::new(EXIT_TEMPLATE_ERROR, format!("{}: {}", fname, e)))?;
+        tables.push(table);
+    }
+    CliTable::try_from_tables(tables).map_err(|e| CliError::new(EXIT_TEMPLATE_ERROR, e.to_string()))
+}
+
+fn run_detect(
+    indexes: &[String],
+    platform: Option<&str>,
+    input: &Option<String>,
+) -> Result<(), CliError> {
+    let cli_table = load_indexes(indexes)?;
+    let text = read_input(input)?;
+    let scores = cli_table.detect_template(&text, platform);
+    if scores.first().is_none_or(|s| s.score == 0.0) {
+        return Err(CliError::new(EXIT_FAILURE, "no template fits the input"));
+    }
+    for s in scores.iter().filter(|s| s.score > 0.0) {
+        println!(
+            "{:.3}  {}  records: {}, lines: {:.0}%, errors: {}",
+            s.score,
+            s.template.display(),
+            s.records,
+            s.line_coverage * 100.0,
+            s.error_transitions
+        );
+    }
+    Ok(())
+}
+
+fn run_parse_cmd(
+    indexes: &[String],
+    attributes: HashMap<String, String>,
+    metadata: &Metadata,
+    input: &Option<String>,
+    format: Format,
+) -> Result<(), CliError> {
+    let cli_table = load_indexes(indexes)?;
+    if cli_table.get_template_for_attributes(&attributes).is_none() {
+        let platform = attributes.get("Platform").cloned().unwrap_or_default();
+        let command = attributes.get("Command").cloned().unwrap_or_default();
+        let mut message = format!(
+            "no template for platform '{}' command '{}'",
+            platform, command
+        );
+        let suggestions = cli_table.suggest(&platform, &command, 3);
+        for s in suggestions.platforms {
+            message.push_str(&format!("\n  did you mean platform '{}'?", s.value));
+        }
+        for s in suggestions.commands {
+            message.push_str(&format!("\n  did you mean '{}'?", s.value));
+        }
+        return Err(CliError::new(EXIT_FAILURE, message));
+    }
+    let text = read_input(input)?;
+    let mut out = cli_table.parse_cmd(&text, &attributes).map_err(|e| {
+        match e.downcast::<TextFSMError>() {
+            Ok(e) => parse_error(*e),
+            Err(e) => CliError::new(EXIT_TEMPLATE_ERROR, e.to_string()),
+        }
+    })?;
+    metadata
+        .apply(&mut out)
+        .map_err(|e| CliError::new(EXIT_FAILURE, e))?;
+    output(&out, format)
+}
+
+fn run_validate(indexes: &[String], templates: &[String]) -> Result<(), CliError> {
+    let mut error_count = 0;
+    for fname in indexes {
+        match ParsedCliTable::try_from_file(fname) {
+            Ok(table) => {
+                let report = table.validate();
+                error_count += report.errors().count();
+                if report.issues.is_empty() {
+                    println!("{}: ok", fname);
+                } else {
+                    print!("{}", report);
+                }
+            }
+            Err(e) => {
+                error_count += 1;
+                println!("{}: error: {}", fname, e);
+            }
+        }
+    }
+    for fname in templates {
+        match load_template(fname) {
+            Ok(_) => println!("{}: ok", fname),
+            Err(e) => {
+                error_count += 1;
+                println!("error: {}", e.message);
+            }
+        }
+    }
+    if error_count > 0 {
+        return Err(CliError::new(
+            EXIT_FAILURE,
+            format!("validation failed with {} error(s)", error_count),
+        ));
+    }
+    Ok(())
+}
+
+fn run_fmt(template: &str, check: bool, write: bool) -> Result<(), CliError> {
+    let text = std::fs::read_to_string(template)
+        .map_err(|e| CliError::new(EXIT_TEMPLATE_ERROR, format!("{}: {}", template, e)))?;
+    let formatted = format_template(&text)
+        .map_err(|e| CliError::new(EXIT_TEMPLATE_ERROR, format!("{}: {}", template, e)))?;
+    if check {
+        if formatted != text {
+            return Err(CliError::new(
+                EXIT_FAILURE,
+                format!("{}: not formatted", template),
+            ));
+        }
+    } else if write {
+        if formatted != text {
+            std::fs::write(template, formatted)
+                .map_err(|e| CliError::new(EXIT_FAILURE, format!("{}: {}", template, e)))?;
+        }
+    } else {
+        print!("{}", formatted);
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ParsedSample {
+    parsed_sample: Vec<DataRecord>,
+}
+
+fn run_test(template: &str, input: &str, expected: &str) -> Result<(), CliError> {
+    let parser = load_template(template)?;
+    let text = read_input(&Some(input.to_string()))?;
+    let yaml = std::fs::read_to_string(expected)
+        .map_err(|e| CliError::new(EXIT_FAILURE, format!("{}: {}", expected, e)))?;
+    let sample: ParsedSample = serde_yaml::from_str(&yaml)
+        .map_err(|e| CliError::new(EXIT_FAILURE, format!("{}: {}", expected, e)))?;
+    let mut textfsm = TextFSM::from_parser(parser);
+    let result = textfsm
+        .try_parse_string(&text, Some(DataRecordConversion::LowercaseKeys))
+        .map_err(parse_error)?;
+    if result == sample.parsed_sample {
+        println!("{}: ok", input);
+        return Ok(());
+    }
+    let (only_in_parse, only_in_yaml) = DataRecord::compare_sets(&result, &sample.parsed_sample);
+    let mismatch_count: usize = only_in_parse
+        .iter()
+        .chain(only_in_yaml.iter())
+        .map(|x| x.len())
+        .sum();
+    if mismatch_count == 0 && result.len() == sample.parsed_sample.len() {
+        println!("{}: ok", input);
+        return Ok(());
+    }
+    println!("{}: results differ", input);
+    for (i, (parsed, yaml)) in only_in_parse.iter().zip(only_in_yaml.iter()).enumerate() {
+        if !parsed.is_empty() || !yaml.is_empty() {
+            println!("  record {}: parsed {:?}, expected {:?}", i, parsed, yaml);
+        }
+    }
+    if result.len() != sample.parsed_sample.len() {
+        println!(
+            "  parsed {} records, expected {}",
+            result.len(),
+            sample.parsed_sample.len()
+        );
+    }
+    Err(CliError::new(
+        EXIT_FAILURE,
+        format!("{}: test failed", input),
+    ))
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    match cli.command {
+        Command::Parse {
+            template,
+            input,
+            lenient,
+            encoding,
+            clean,
+        } => run_parse(&template, &input, lenient, encoding, clean, cli.format),
+        Command::ParseCmd {
+            indexes,
+            platform,
+            command,
+            attributes,
+            metadata,
+            input,
+        } => {
+            let mut attributes: HashMap<String, String> = attributes.into_iter().collect();
+            attributes.insert("Platform".to_string(), platform);
+            attributes.insert("Command".to_string(), command);
+            let metadata = metadata
+                .iter()
+                .fold(Metadata::new(), |m, (name, value)| m.with(name, value));
+            run_parse_cmd(&indexes, attributes, &metadata, &input, cli.format)
+        }
+        Command::Detect {
+            indexes,
+            platform,
+            input,
+        } => run_detect(&indexes, platform.as_deref(), &input),
+        Command::Validate { indexes, templates } => run_validate(&indexes, &templates),
+        Command::Fmt {
+            template,
+            check,
+            write,
+        } => run_fmt(&template, check, write),
+        Command::Test {
+            template,
+            input,
+            expected,
+        } => run_test(&template, &input, &expected),
+        Command::Dot { template } => {
+            let parser = load_template(&template)?;
+            print!("{}", parser.to_dot());
+            Ok(())
+        }
+        Command::Schema { template, typed } => {
+            let parser = load_template(&template)?;
+            let title = std::path::Path::new(&template)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string());
+            let schema = parser.json_schema(&SchemaOptions { title, typed });
+            println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("textfsm: {}", e.message);
+            ExitCode::from(e.code)
+        }
+    }
+}
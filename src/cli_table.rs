@@ -1,11 +1,17 @@
+use crate::metadata::Metadata;
+use crate::{DataRecord, ParsedOutput, TemplateCache, TextFSM, TextFSMParser, TextTable, Value};
 use fancy_regex::Regex;
+use log::debug;
 use std::collections::HashMap;
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct ParsedCliTable {
     pub fname: String,
+    /// Column names in the order of the index file
+    pub header: Vec<String>,
     pub rows: Vec<CliTableRow>,
 }
 
@@ -13,6 +19,10 @@ pub struct ParsedCliTable {
 pub struct CliTable {
     pub tables: Vec<ParsedCliTable>,
     pub platform_regex_rules: HashMap<String, Vec<CliTableRegexRule>>,
+    /// Rules for all the rows, in the lookup order
+    pub regex_rules: Vec<CliTableRegexRule>,
+    /// Compiled templates, shared between the clones of this table
+    pub template_cache: Arc<TemplateCache>,
 }
 
 #[derive(Debug, Clone)]
@@ -20,6 +30,8 @@ pub struct CliTableRegexRule {
     pub table_index: usize,
     pub row_index: usize,
     pub command_regex: Regex,
+    /// Regexes for every non-Template column that has a value, anchored at the start
+    pub attribute_regexes: HashMap<String, Regex>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,10 +40,212 @@ pub struct CliTableRow {
     pub hostname: Option<String>,
     pub platform: Option<String>,
     pub command: String,
+    /// All the columns of the row, including the ones above
+    pub columns: HashMap<String, String>,
+}
+
+/// Columns with a special meaning in the index or used by the upstream indexes,
+/// the others are plain regex attributes
+pub const KNOWN_INDEX_COLUMNS: &[&str] = &[
+    "Template", "Hostname", "Vendor", "Model", "Platform", "Command",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexIssueKind {
+    UnknownColumn(String),
+    EmptyTemplateName,
+    MissingTemplate(String),
+    TemplateError {
+        template: String,
+        message: String,
+    },
+    BadCompletion(String),
+    BadRegex {
+        column: String,
+        message: String,
+    },
+    /// Same command and attributes as an earlier row
+    DuplicateRow {
+        first_row_index: usize,
+    },
+    /// An earlier row always matches first, so this one can never be selected
+    ShadowedRow {
+        by_row_index: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexIssue {
+    pub severity: IssueSeverity,
+    /// Index of the row in `ParsedCliTable::rows`, `None` for the issues of the header
+    pub row_index: Option<usize>,
+    pub kind: IndexIssueKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub fname: String,
+    pub issues: Vec<IndexIssue>,
+}
+
+impl std::fmt::Display for IndexIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexIssueKind::UnknownColumn(c) => write!(f, "unknown column '{}'", c),
+            IndexIssueKind::EmptyTemplateName => write!(f, "empty template name"),
+            IndexIssueKind::MissingTemplate(t) => write!(f, "template {} does not exist", t),
+            IndexIssueKind::TemplateError { template, message } => {
+                write!(f, "template {} does not compile: {}", template, message)
+            }
+            IndexIssueKind::BadCompletion(msg) => write!(f, "bad completion: {}", msg),
+            IndexIssueKind::BadRegex { column, message } => {
+                write!(f, "bad regex in column {}: {}", column, message)
+            }
+            IndexIssueKind::DuplicateRow { first_row_index } => {
+                write!(f, "duplicate of row {}", first_row_index + 1)
+            }
+            IndexIssueKind::ShadowedRow { by_row_index } => {
+                write!(f, "never selected, shadowed by row {}", by_row_index + 1)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for IndexIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            IssueSeverity::Warning => "warning",
+            IssueSeverity::Error => "error",
+        };
+        match self.row_index {
+            // rows are numbered from 1 for humans
+            Some(i) => write!(f, "{}: row {}: {}", severity, i + 1, self.kind),
+            None => write!(f, "{}: {}", severity, self.kind),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}: {}", &self.fname, issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &IndexIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &IndexIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Warning)
+    }
+
+    fn add(&mut self, severity: IssueSeverity, row_index: Option<usize>, kind: IndexIssueKind) {
+        self.issues.push(IndexIssue {
+            severity,
+            row_index,
+            kind,
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub value: String,
+    /// Edit distance from what was asked for, lower is closer
+    pub distance: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Suggestions {
+    /// Closest known platforms, only when the platform is not in the index
+    pub platforms: Vec<Suggestion>,
+    /// Closest canonical commands of the platform, or of the closest known platform
+    pub commands: Vec<Suggestion>,
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + if ca == *cb { 0 } else { 1 };
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// Distance between an abbreviated command and a canonical one, word by word:
+/// a word which is a prefix of the canonical word costs nothing.
+fn command_distance(command: &str, canonical: &str) -> usize {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let canonical_words: Vec<&str> = canonical.split_whitespace().collect();
+    let word_distance = |w: &str, cw: &str| {
+        let prefix: String = cw.chars().take(w.chars().count()).collect();
+        levenshtein(w, &prefix).min(levenshtein(w, cw))
+    };
+    let mut prev: Vec<usize> = vec![0];
+    for cw in &canonical_words {
+        prev.push(prev.last().unwrap() + cw.len());
+    }
+    for w in &words {
+        let mut curr = vec![prev[0] + w.len()];
+        for (j, cw) in canonical_words.iter().enumerate() {
+            let substitution = prev[j] + word_distance(w, cw);
+            curr.push(
+                substitution
+                    .min(prev[j + 1] + w.len())
+                    .min(curr[j] + cw.len()),
+            );
+        }
+        prev = curr;
+    }
+    prev[canonical_words.len()]
+}
+
+fn rank_suggestions(
+    asked: &str,
+    candidates: Vec<String>,
+    distance: impl Fn(&str, &str) -> usize,
+    limit: usize,
+) -> Vec<Suggestion> {
+    let max_distance = std::cmp::max(2, asked.len() / 2);
+    let mut suggestions: Vec<Suggestion> = candidates
+        .into_iter()
+        .map(|value| Suggestion {
+            distance: distance(asked, &value),
+            value,
+        })
+        .filter(|s| s.distance <= max_distance)
+        .collect();
+    // stable sort keeps the index order for the same distance
+    suggestions.sort_by_key(|s| s.distance);
+    suggestions.truncate(limit);
+    suggestions
 }
 
 impl ParsedCliTable {
-    fn example(fname: &str) -> Result<Vec<CliTableRow>, Box<dyn Error>> {
+    fn example(fname: &str) -> Result<(Vec<String>, Vec<CliTableRow>), Box<dyn Error>> {
         use std::io::BufReader;
         let file = std::fs::File::open(fname)?;
         let reader = BufReader::new(file);
@@ -42,10 +256,10 @@ impl ParsedCliTable {
             .delimiter(b',')
             .trim(csv::Trim::All)
             .from_reader(reader);
-        println!("Reader");
 
-        let headers: Vec<&str> = rdr.headers()?.into_iter().collect();
-        println!("Headers: {:?}", &headers);
+        let header: Vec<String> = rdr.headers()?.iter().map(|x| x.to_string()).collect();
+        let headers: Vec<&str> = header.iter().map(|x| x.as_str()).collect();
+        debug!("Headers: {:?}", &headers);
 
         if !headers.contains(&"Template") {
             return Err("No template".into());
@@ -70,24 +284,207 @@ impl ParsedCliTable {
                 .map(|x| x.to_string())
                 .collect();
             let command = record[command_position].to_string();
+            let columns: HashMap<String, String> = headers
+                .iter()
+                .zip(record.iter())
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
 
             let row = CliTableRow {
                 templates,
                 hostname,
                 platform,
                 command,
+                columns,
             };
             rows.push(row);
         }
-        Ok(rows)
+        Ok((header, rows))
     }
     pub fn from_file(fname: &str) -> Self {
-        println!("Loading cli table from {}", &fname);
-        let rows = Self::example(fname).unwrap();
-        ParsedCliTable {
+        Self::try_from_file(fname).unwrap()
+    }
+
+    pub fn try_from_file(fname: &str) -> Result<Self, Box<dyn Error>> {
+        debug!("Loading cli table from {}", &fname);
+        let (header, rows) = Self::example(fname)?;
+        Ok(ParsedCliTable {
             fname: fname.to_string(),
+            header,
             rows,
+        })
+    }
+
+    fn check_completions(command: &str) -> Result<(), String> {
+        let mut rest = command;
+        while let Some(start) = rest.find("[[") {
+            let content = &rest[start + 2..];
+            let end = content
+                .find("]]")
+                .ok_or_else(|| format!("unterminated '[[' in '{}'", command))?;
+            if end == 0 {
+                return Err(format!("empty '[[]]' in '{}'", command));
+            }
+            if content[..end].contains("[[") {
+                return Err(format!("nested '[[' in '{}'", command));
+            }
+            rest = &content[end + 2..];
+        }
+        if rest.contains("]]") {
+            return Err(format!("']]' without '[[' in '{}'", command));
+        }
+        Ok(())
+    }
+
+    /// An earlier row with these attribute cells matches whatever the later one does.
+    fn attributes_cover(&self, earlier: &CliTableRow, later: &CliTableRow) -> bool {
+        self.header
+            .iter()
+            .filter(|c| *c != "Template" && *c != "Command")
+            .all(|c| {
+                let earlier_cell = earlier.columns.get(c).map(|x| x.as_str()).unwrap_or("");
+                let later_cell = later.columns.get(c).map(|x| x.as_str()).unwrap_or("");
+                earlier_cell.is_empty() || earlier_cell == ".*" || earlier_cell == later_cell
+            })
+    }
+
+    /// Check the columns, the completions and the regexes of every row, that the
+    /// templates exist and compile, and find the rows that can never be selected.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport {
+            fname: self.fname.clone(),
+            ..Default::default()
+        };
+        for column in &self.header {
+            if !KNOWN_INDEX_COLUMNS.contains(&column.as_str()) {
+                report.add(
+                    IssueSeverity::Warning,
+                    None,
+                    IndexIssueKind::UnknownColumn(column.clone()),
+                );
+            }
+        }
+
+        let template_dir = CliTable::get_directory(&self.fname).unwrap_or_default();
+        let mut compiled_templates: HashMap<String, Result<(), String>> = HashMap::new();
+        // anchored command regexes of the rows seen so far, if they compiled
+        let mut command_regexes: Vec<Option<Regex>> = vec![];
+
+        for (row_index, row) in self.rows.iter().enumerate() {
+            for template in &row.templates {
+                if template.is_empty() {
+                    report.add(
+                        IssueSeverity::Error,
+                        Some(row_index),
+                        IndexIssueKind::EmptyTemplateName,
+                    );
+                    continue;
+                }
+                let path = Path::new(&template_dir).join(template);
+                if !path.exists() {
+                    report.add(
+                        IssueSeverity::Error,
+                        Some(row_index),
+                        IndexIssueKind::MissingTemplate(path.to_string_lossy().into_owned()),
+                    );
+                    continue;
+                }
+                let result = compiled_templates
+                    .entry(template.clone())
+                    .or_insert_with(|| {
+                        let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+                        TextFSMParser::from_string(&text).map(|_| ())
+                    });
+                if let Err(message) = result {
+                    report.add(
+                        IssueSeverity::Error,
+                        Some(row_index),
+                        IndexIssueKind::TemplateError {
+                            template: path.to_string_lossy().into_owned(),
+                            message: message.clone(),
+                        },
+                    );
+                }
+            }
+
+            let completions_ok = match Self::check_completions(&row.command) {
+                Ok(()) => true,
+                Err(msg) => {
+                    report.add(
+                        IssueSeverity::Error,
+                        Some(row_index),
+                        IndexIssueKind::BadCompletion(msg),
+                    );
+                    false
+                }
+            };
+
+            let mut command_regex: Option<Regex> = None;
+            for column in &self.header {
+                let value = row.columns.get(column).map(|x| x.as_str()).unwrap_or("");
+                // a bad completion is already reported, the regex would be bad too
+                let bad_command = column == "Command" && !completions_ok;
+                if column == "Template" || value.is_empty() || bad_command {
+                    continue;
+                }
+                let value = if column == "Command" {
+                    CliTable::expand_brackets(value)
+                } else {
+                    value.to_string()
+                };
+                match Regex::new(&format!("^(?:{})", value)) {
+                    Ok(rx) => {
+                        if column == "Command" {
+                            command_regex = Some(rx);
+                        }
+                    }
+                    Err(e) => report.add(
+                        IssueSeverity::Error,
+                        Some(row_index),
+                        IndexIssueKind::BadRegex {
+                            column: column.clone(),
+                            message: e.to_string(),
+                        },
+                    ),
+                }
+            }
+
+            if completions_ok && command_regex.is_some() {
+                let full = CliTable::canonical_command(&row.command);
+                let shortest = CliTable::shortest_command(&row.command);
+                for (earlier_index, earlier) in self.rows[..row_index].iter().enumerate() {
+                    if !self.attributes_cover(earlier, row) {
+                        continue;
+                    }
+                    if earlier.command == row.command {
+                        report.add(
+                            IssueSeverity::Warning,
+                            Some(row_index),
+                            IndexIssueKind::DuplicateRow {
+                                first_row_index: earlier_index,
+                            },
+                        );
+                        break;
+                    }
+                    if let Some(rx) = &command_regexes[earlier_index] {
+                        if rx.is_match(&full).unwrap_or(false)
+                            && rx.is_match(&shortest).unwrap_or(false)
+                        {
+                            report.add(
+                                IssueSeverity::Warning,
+                                Some(row_index),
+                                IndexIssueKind::ShadowedRow {
+                                    by_row_index: earlier_index,
+                                },
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+            command_regexes.push(command_regex);
         }
+        report
     }
 }
 
@@ -145,11 +542,162 @@ impl CliTable {
         result
     }
 
-    fn get_directory(filename: &str) -> Option<String> {
+    /// The canonical command, with the completions taken in full: `sh[[ow]] ver` -> `show ver`
+    pub fn canonical_command(command: &str) -> String {
+        command.replace("[[", "").replace("]]", "")
+    }
+
+    /// The command with the completions left out: `sh[[ow]] ver` -> `sh ver`
+    fn shortest_command(command: &str) -> String {
+        let mut result = String::new();
+        let mut rest = command;
+        while let Some(start) = rest.find("[[") {
+            result.push_str(&rest[..start]);
+            match rest[start..].find("]]") {
+                Some(end) => rest = &rest[start + end + 2..],
+                None => {
+                    rest = &rest[start..];
+                    break;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    fn row(&self, rule: &CliTableRegexRule) -> &CliTableRow {
+        &self.tables[rule.table_index].rows[rule.row_index]
+    }
+
+    fn rule_matches_platform(rule: &CliTableRegexRule, platform: &str) -> bool {
+        match rule.attribute_regexes.get("Platform") {
+            Some(rx) => rx.is_match(platform).expect("Fancy regex ok?"),
+            None => true,
+        }
+    }
+
+    /// The platforms listed in the index, in the order of their first appearance.
+    pub fn platforms(&self) -> Vec<String> {
+        let mut platforms: Vec<String> = vec![];
+        for rule in &self.regex_rules {
+            if let Some(platform) = &self.row(rule).platform {
+                if !platform.is_empty() && !platforms.contains(platform) {
+                    platforms.push(platform.clone());
+                }
+            }
+        }
+        platforms
+    }
+
+    /// The canonical commands supported by the platform, in the lookup order.
+    pub fn commands_for_platform(&self, platform: &str) -> Vec<String> {
+        let mut commands: Vec<String> = vec![];
+        for rule in &self.regex_rules {
+            if Self::rule_matches_platform(rule, platform) {
+                let command = Self::canonical_command(&self.row(rule).command);
+                if !commands.contains(&command) {
+                    commands.push(command);
+                }
+            }
+        }
+        commands
+    }
+
+    /// The paths of the templates which parse the command, in the order of the index.
+    pub fn templates_for_command(&self, platform: &str, command: &str) -> Option<Vec<PathBuf>> {
+        let (template_dir, row) = self.get_template_for_command_attributes(platform, command)?;
+        let templates = row
+            .templates
+            .iter()
+            .map(|t| Path::new(&template_dir).join(t))
+            .collect();
+        Some(templates)
+    }
+
+    /// The canonical command which an abbreviated one resolves to: `sh ver` -> `show version`
+    pub fn complete_command(&self, platform: &str, command: &str) -> Option<String> {
+        let (_template_dir, row) = self.get_template_for_command_attributes(platform, command)?;
+        Some(Self::canonical_command(&row.command))
+    }
+
+    /// Suggest what could have been meant by a platform and a command without a template.
+    ///
+    /// Returns up to `limit` closest candidates of each kind; the candidates which
+    /// are too far from what was asked for are left out.
+    pub fn suggest(&self, platform: &str, command: &str, limit: usize) -> Suggestions {
+        let mut suggestions = Suggestions::default();
+        let mut platform = platform.to_string();
+        if !self.platforms().contains(&platform) {
+            suggestions.platforms =
+                rank_suggestions(&platform, self.platforms(), levenshtein, limit);
+            match suggestions.platforms.first() {
+                Some(closest) => platform = closest.value.clone(),
+                None => return suggestions,
+            }
+        }
+        suggestions.commands = rank_suggestions(
+            command,
+            self.commands_for_platform(&platform),
+            command_distance,
+            limit,
+        );
+        suggestions
+    }
+
+    fn get_template_for_command_attributes(
+        &self,
+        platform: &str,
+        command: &str,
+    ) -> Option<(String, CliTableRow)> {
+        let attributes: HashMap<String, String> = [
+            ("Platform".to_string(), platform.to_string()),
+            ("Command".to_string(), command.to_string()),
+        ]
+        .into_iter()
+        .collect();
+        self.get_template_for_attributes(&attributes)
+    }
+
+    /// Validate every index file of the table, see `ParsedCliTable::validate`.
+    pub fn validate(&self) -> Vec<ValidationReport> {
+        self.tables.iter().map(|t| t.validate()).collect()
+    }
+
+    pub(crate) fn get_directory(filename: &str) -> Option<String> {
         let path = Path::new(filename);
         path.parent().map(|p| p.to_string_lossy().into_owned())
     }
 
+    /// Find the first row where each of the given attributes matches its column.
+    ///
+    /// Like in Python's clitable, every column except `Template` is a regex which must
+    /// match at the start of the attribute value, the `[[...]]` completions in `Command`
+    /// are expanded, and the attributes without a column or with an empty cell are
+    /// skipped. Rows are tried in the order of the index.
+    pub fn get_template_for_attributes(
+        &self,
+        attributes: &HashMap<String, String>,
+    ) -> Option<(String, CliTableRow)> {
+        for rule in &self.regex_rules {
+            let matches =
+                attributes
+                    .iter()
+                    .all(|(key, value)| match rule.attribute_regexes.get(key) {
+                        Some(rx) => rx.is_match(value).expect("Fancy regex ok?"),
+                        None => true,
+                    });
+            if matches {
+                let row = self.tables[rule.table_index].rows[rule.row_index].clone();
+                let fname = &self.tables[rule.table_index].fname;
+                if let Some(fdir) = Self::get_directory(fname) {
+                    return Some((fdir, row));
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the template by the exact platform name and the command.
     pub fn get_template_for_command(
         &self,
         platform: &str,
@@ -169,21 +717,207 @@ impl CliTable {
         None
     }
 
+    /// Use the given template cache, e.g. to share it between several tables.
+    pub fn with_template_cache(mut self, template_cache: Arc<TemplateCache>) -> Self {
+        self.template_cache = template_cache;
+        self
+    }
+
+    pub fn get_parser(&self, template_path: &Path) -> Result<Arc<TextFSMParser>, Box<dyn Error>> {
+        Ok(self.template_cache.get(template_path)?)
+    }
+
+    /// Parse the output of a command, equivalent of Python's `clitable.ParseCmd`.
+    ///
+    /// The `attributes` must contain the `Command`, and may contain the `Platform`,
+    /// `Hostname` or any other index column, see `get_template_for_attributes`.
+    /// When the index lists several templates for the command, the columns of the
+    /// later ones are added to the records of the first one with the same values
+    /// of the shared Key columns, or at the same position when there are none.
+    /// The header is the union of their values.
+    pub fn parse_cmd(
+        &self,
+        text: &str,
+        attributes: &HashMap<String, String>,
+    ) -> Result<ParsedOutput, Box<dyn Error>> {
+        self.parse_cmd_with_keys(text, attributes)
+            .map(|(out, _)| out)
+    }
+
+    /// Like `parse_cmd`, with the metadata columns set in every record and in
+    /// front of the header.
+    pub fn parse_cmd_with_metadata(
+        &self,
+        text: &str,
+        attributes: &HashMap<String, String>,
+        metadata: &Metadata,
+    ) -> Result<ParsedOutput, Box<dyn Error>> {
+        let mut out = self.parse_cmd(text, attributes)?;
+        metadata.apply(&mut out)?;
+        Ok(out)
+    }
+
+    /// Like `parse_cmd`, returning a table with the Key values of the templates as
+    /// the superkey.
+    pub fn parse_cmd_table(
+        &self,
+        text: &str,
+        attributes: &HashMap<String, String>,
+    ) -> Result<TextTable, Box<dyn Error>> {
+        let (out, keys) = self.parse_cmd_with_keys(text, attributes)?;
+        let mut table = TextTable::from(out);
+        table.set_superkey(keys)?;
+        Ok(table)
+    }
+
+    fn parse_cmd_with_keys(
+        &self,
+        text: &str,
+        attributes: &HashMap<String, String>,
+    ) -> Result<(ParsedOutput, Vec<String>), Box<dyn Error>> {
+        let command = attributes
+            .get("Command")
+            .ok_or("attribute 'Command' is required")?;
+        let (template_dir, row) =
+            self.get_template_for_attributes(attributes)
+                .ok_or_else(|| {
+                    format!(
+                        "no template found for command '{}' with attributes {:?}",
+                        command, attributes
+                    )
+                })?;
+
+        let mut out = ParsedOutput::default();
+        let mut keys: Vec<String> = vec![];
+        for (i, short_template_name) in row.templates.iter().enumerate() {
+            let template_path = Path::new(&template_dir).join(short_template_name);
+            let parser = self.get_parser(&template_path)?;
+            let mut textfsm = TextFSM::from_parser(parser);
+            let new_records = textfsm.try_parse_string(text, None)?;
+            let template_keys = textfsm.key_names();
+            let new_columns: Vec<String> = textfsm
+                .header()
+                .into_iter()
+                .filter(|name| !out.header.contains(name))
+                .collect();
+            if i == 0 {
+                out.records = new_records;
+            } else {
+                // like Python's texttable.extend: rows match on the values of the
+                // shared Key columns, or by position when there are none
+                let shared_keys: Vec<&String> = template_keys
+                    .iter()
+                    .filter(|name| out.header.contains(name))
+                    .collect();
+                let copy_columns = |res: &mut DataRecord, nrow: &DataRecord| {
+                    for name in &new_columns {
+                        if let Some(value) = nrow.get(name) {
+                            res.fields.insert(name.clone(), value.clone());
+                        }
+                    }
+                };
+                if shared_keys.is_empty() {
+                    for (res, nrow) in out.records.iter_mut().zip(new_records.iter()) {
+                        copy_columns(res, nrow);
+                    }
+                } else {
+                    for res in out.records.iter_mut() {
+                        if let Some(nrow) = new_records
+                            .iter()
+                            .find(|nrow| shared_keys.iter().all(|k| res.get(k) == nrow.get(k)))
+                        {
+                            copy_columns(res, nrow);
+                        }
+                    }
+                }
+            }
+            for name in template_keys {
+                if !keys.contains(&name) {
+                    keys.push(name);
+                }
+            }
+            out.header.extend(new_columns);
+        }
+        for rec in out.records.iter_mut() {
+            for name in &out.header {
+                if rec.get(name).is_none() {
+                    rec.fields
+                        .insert(name.clone(), Value::Single(String::new()));
+                }
+            }
+        }
+        Ok((out, keys))
+    }
+
     pub fn from_file(fname: &str) -> Self {
-        let parsed_cli_table = ParsedCliTable::from_file(fname);
-        let tables = vec![parsed_cli_table];
+        Self::from_files(&[fname])
+    }
+
+    /// Load several index files into one table.
+    ///
+    /// The files are listed from the highest precedence to the lowest: the rows of
+    /// an earlier file are tried first, so a local index can shadow or extend the
+    /// commands of an upstream one. Each file resolves its templates relative to its
+    /// own directory.
+    pub fn from_files(fnames: &[&str]) -> Self {
+        Self::try_from_files(fnames).unwrap()
+    }
+
+    /// Like `from_files`, reporting the files that can not be read and the bad
+    /// regexes of the rows instead of panicking.
+    pub fn try_from_files(fnames: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let tables = fnames
+            .iter()
+            .map(|fname| ParsedCliTable::try_from_file(fname))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::try_from_tables(tables)
+    }
+
+    pub fn from_tables(tables: Vec<ParsedCliTable>) -> Self {
+        Self::try_from_tables(tables).unwrap()
+    }
+
+    pub fn try_from_tables(tables: Vec<ParsedCliTable>) -> Result<Self, Box<dyn Error>> {
         let mut platform_regex_rules: HashMap<String, Vec<CliTableRegexRule>> = Default::default();
+        let mut regex_rules: Vec<CliTableRegexRule> = vec![];
 
         for (table_index, table) in tables.iter().enumerate() {
             for (row_index, row) in table.rows.iter().enumerate() {
                 let expanded_command = Self::expand_brackets(&row.command);
-                let command_regex = Regex::new(&expanded_command).unwrap();
+                // rows are numbered from 1 for humans
+                let bad_regex = |column: &str, e: fancy_regex::Error| {
+                    format!(
+                        "{}: row {}: bad regex in column {}: {}",
+                        table.fname,
+                        row_index + 1,
+                        column,
+                        e
+                    )
+                };
+                let command_regex =
+                    Regex::new(&expanded_command).map_err(|e| bad_regex("Command", e))?;
+                let mut attribute_regexes: HashMap<String, Regex> = HashMap::new();
+                for (column, value) in &row.columns {
+                    if column == "Template" || value.is_empty() {
+                        continue;
+                    }
+                    let value = if column == "Command" {
+                        &expanded_command
+                    } else {
+                        value
+                    };
+                    let rx =
+                        Regex::new(&format!("^(?:{})", value)).map_err(|e| bad_regex(column, e))?;
+                    attribute_regexes.insert(column.clone(), rx);
+                }
 
                 let rule = CliTableRegexRule {
                     table_index,
                     row_index,
                     command_regex,
+                    attribute_regexes,
                 };
+                regex_rules.push(rule.clone());
                 let no_platform = format!("no-platform");
                 let platform_name: &str = row.platform.as_ref().unwrap_or(&no_platform);
                 platform_regex_rules
@@ -192,9 +926,11 @@ impl CliTable {
                     .push(rule);
             }
         }
-        CliTable {
+        Ok(CliTable {
             platform_regex_rules,
+            regex_rules,
             tables,
-        }
+            template_cache: Default::default(),
+        })
     }
 }
@@ -0,0 +1,118 @@
+use crate::{DataRecord, DataRecordConversion, ParsedOutput};
+use std::fmt;
+
+/// Custom conversion of the record keys, see `DataRecordConversion::Custom`.
+pub trait KeyConverter: Send + Sync {
+    fn convert_key(&self, key: &str) -> String;
+}
+
+impl<F> KeyConverter for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn convert_key(&self, key: &str) -> String {
+        self(key)
+    }
+}
+
+/// `snake_case` of a name in `UPPER_SNAKE_CASE`, `CamelCase` or `mixedCase`.
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // a word starts at an upper case letter after a lower case one or a digit,
+            // or at the last letter of an acronym followed by a lower case one: IPAddress
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                out.push('_');
+            }
+        }
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out
+}
+
+impl DataRecordConversion {
+    pub fn convert_key(&self, key: &str) -> String {
+        match self {
+            DataRecordConversion::LowercaseKeys => key.to_lowercase(),
+            DataRecordConversion::UppercaseKeys => key.to_uppercase(),
+            DataRecordConversion::SnakeCaseKeys => snake_case(key),
+            DataRecordConversion::RenameKeys(names) => {
+                names.get(key).cloned().unwrap_or_else(|| key.to_string())
+            }
+            DataRecordConversion::PrefixKeys(prefix) => format!("{}{}", prefix, key),
+            DataRecordConversion::Custom(converter) => converter.convert_key(key),
+            DataRecordConversion::Chain(conversions) => {
+                conversions.iter().fold(key.to_string(), |key, conversion| {
+                    conversion.convert_key(&key)
+                })
+            }
+        }
+    }
+
+    pub fn convert_record(&self, rec: &DataRecord) -> DataRecord {
+        let mut out = DataRecord::new();
+        out.record_key = rec.record_key.clone();
+        for (k, v) in rec.iter() {
+            out.fields.insert(self.convert_key(k), v.clone());
+        }
+        if let Some(ref provenance) = rec.provenance {
+            let mut provenance = provenance.clone();
+            provenance.values = provenance
+                .values
+                .into_iter()
+                .map(|(k, captures)| (self.convert_key(&k), captures))
+                .collect();
+            out.provenance = Some(provenance);
+        }
+        out
+    }
+
+    pub fn convert_records(&self, records: &[DataRecord]) -> Vec<DataRecord> {
+        records.iter().map(|rec| self.convert_record(rec)).collect()
+    }
+}
+
+impl fmt::Debug for DataRecordConversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataRecordConversion::LowercaseKeys => write!(f, "LowercaseKeys"),
+            DataRecordConversion::UppercaseKeys => write!(f, "UppercaseKeys"),
+            DataRecordConversion::SnakeCaseKeys => write!(f, "SnakeCaseKeys"),
+            DataRecordConversion::RenameKeys(names) => {
+                f.debug_tuple("RenameKeys").field(names).finish()
+            }
+            DataRecordConversion::PrefixKeys(prefix) => {
+                f.debug_tuple("PrefixKeys").field(prefix).finish()
+            }
+            DataRecordConversion::Custom(_) => write!(f, "Custom(..)"),
+            DataRecordConversion::Chain(conversions) => {
+                f.debug_tuple("Chain").field(conversions).finish()
+            }
+        }
+    }
+}
+
+impl ParsedOutput {
+    /// Convert the names of the header and the keys of the records.
+    pub fn convert_keys(&self, conversion: &DataRecordConversion) -> ParsedOutput {
+        ParsedOutput {
+            header: self
+                .header
+                .iter()
+                .map(|name| conversion.convert_key(name))
+                .collect(),
+            records: conversion.convert_records(&self.records),
+        }
+    }
+}
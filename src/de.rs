@@ -0,0 +1,513 @@
+//! Deserialization of records into user types.
+//!
+//! The Value names are matched with the struct fields ignoring the case, so a
+//! `MTU` value fills a `mtu` field. The strings are coerced into the type of the
+//! field: integers, floats, bools, IP addresses or anything else deserializable
+//! from a string, and an empty value is `None` for an `Option` field.
+
+use crate::typed::parse_bool;
+use crate::{DataRecord, ParsedOutput, Value};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+/// Failure to deserialize a record, with the record and the field where it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeserializeError {
+    /// Index of the record in the list being deserialized
+    pub record: Option<usize>,
+    /// Value name in the record
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(record) = self.record {
+            write!(f, "record {}, ", record)?;
+        }
+        if let Some(ref field) = self.field {
+            write!(f, "field {}: ", field)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeserializeError {
+            record: None,
+            field: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+pub fn from_value<'de, T: Deserialize<'de>>(value: &'de Value) -> Result<T, DeserializeError> {
+    T::deserialize(ValueDeserializer::new(value))
+}
+
+pub fn from_record<'de, T: Deserialize<'de>>(
+    record: &'de DataRecord,
+) -> Result<T, DeserializeError> {
+    T::deserialize(RecordDeserializer::new(record))
+}
+
+pub fn from_records<'de, T: Deserialize<'de>>(
+    records: &'de [DataRecord],
+) -> Result<Vec<T>, DeserializeError> {
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            from_record(record).map_err(|mut e| {
+                e.record = Some(index);
+                e
+            })
+        })
+        .collect()
+}
+
+impl DataRecord {
+    pub fn deserialize_as<'de, T: Deserialize<'de>>(&'de self) -> Result<T, DeserializeError> {
+        from_record(self)
+    }
+}
+
+impl ParsedOutput {
+    pub fn deserialize_as<'de, T: Deserialize<'de>>(&'de self) -> Result<Vec<T>, DeserializeError> {
+        from_records(&self.records)
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident($ty:ty, $what:expr);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+                match self.0.trim().parse::<$ty>() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::custom(format!("invalid {} '{}'", $what, self.0))),
+                }
+            }
+        )*
+    };
+}
+
+/// A string value, coerced into the requested type.
+struct StrDeserializer<'de>(&'de String);
+
+impl<'de> StrDeserializer<'de> {
+    fn as_seq(&self) -> ListAccess<'de> {
+        let items: &'de [String] = if self.0.is_empty() {
+            &[]
+        } else {
+            std::slice::from_ref(self.0)
+        };
+        ListAccess { iter: items.iter() }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for StrDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match parse_bool(self.0) {
+            Some(b) => visitor.visit_bool(b),
+            None => Err(de::Error::custom(format!("invalid bool '{}'", self.0))),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8(i8, "integer");
+        deserialize_i16 => visit_i16(i16, "integer");
+        deserialize_i32 => visit_i32(i32, "integer");
+        deserialize_i64 => visit_i64(i64, "integer");
+        deserialize_i128 => visit_i128(i128, "integer");
+        deserialize_u8 => visit_u8(u8, "integer");
+        deserialize_u16 => visit_u16(u16, "integer");
+        deserialize_u32 => visit_u32(u32, "integer");
+        deserialize_u64 => visit_u64(u64, "integer");
+        deserialize_u128 => visit_u128(u128, "integer");
+        deserialize_f32 => visit_f32(f32, "number");
+        deserialize_f64 => visit_f64(f64, "number");
+        deserialize_char => visit_char(char, "character");
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_bytes(self.0.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_bytes(self.0.as_bytes())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        if self.0.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(de::Error::custom(format!(
+                "expected empty value, found '{}'",
+                self.0
+            )))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_seq(self.as_seq())
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_seq(self.as_seq())
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_seq(self.as_seq())
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, DeserializeError> {
+        Err(de::Error::custom(format!(
+            "expected a record, found '{}'",
+            self.0
+        )))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_enum(self.0.as_str().into_deserializer())
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+}
+
+struct ListAccess<'de> {
+    iter: std::slice::Iter<'de, String>,
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeserializeError> {
+        match self.iter.next() {
+            Some(item) => seed.deserialize(StrDeserializer(item)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+macro_rules! forward_to_str {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, DeserializeError> {
+                self.single()?.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+/// Deserializer of a single Value: a string, or a sequence for a List value.
+pub struct ValueDeserializer<'de> {
+    value: &'de Value,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    pub fn new(value: &'de Value) -> Self {
+        ValueDeserializer { value }
+    }
+
+    /// The string of a Single value, or of a List with exactly one item.
+    fn single(&self) -> Result<StrDeserializer<'de>, DeserializeError> {
+        match self.value {
+            Value::Single(s) => Ok(StrDeserializer(s)),
+            Value::List(l) if l.len() == 1 => Ok(StrDeserializer(&l[0])),
+            Value::List(l) => Err(de::Error::custom(format!(
+                "expected a single value, found a list of {}",
+                l.len()
+            ))),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self.value {
+            Value::Single(s) => visitor.visit_borrowed_str(s),
+            Value::List(l) => visitor.visit_seq(ListAccess { iter: l.iter() }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self.value {
+            Value::Single(s) if s.is_empty() => visitor.visit_none(),
+            Value::List(l) if l.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self.value {
+            Value::Single(s) => StrDeserializer(s).deserialize_seq(visitor),
+            Value::List(l) => visitor.visit_seq(ListAccess { iter: l.iter() }),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_str! {
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_unit()
+    }
+}
+
+/// Deserializer of a record into a struct or a map.
+pub struct RecordDeserializer<'de> {
+    record: &'de DataRecord,
+}
+
+impl<'de> RecordDeserializer<'de> {
+    pub fn new(record: &'de DataRecord) -> Self {
+        RecordDeserializer { record }
+    }
+
+    /// Value name of the record for a struct field: the exact name, or the one
+    /// differing only by case.
+    fn find_field(&self, field: &str) -> Option<(&'de String, &'de Value)> {
+        let fields = &self.record.fields;
+        fields.get_key_value(field).or_else(|| {
+            fields
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(field))
+        })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for RecordDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let mut entries: Vec<(&'de str, &'de String, &'de Value)> = self
+            .record
+            .fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), name, value))
+            .collect();
+        entries.sort_by_key(|(key, _, _)| *key);
+        visitor.visit_map(RecordAccess {
+            entries: entries.into_iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        let entries: Vec<(&'de str, &'de String, &'de Value)> = fields
+            .iter()
+            .filter_map(|field| {
+                self.find_field(field)
+                    .map(|(name, value)| (*field, name, value))
+            })
+            .collect();
+        visitor.visit_map(RecordAccess {
+            entries: entries.into_iter(),
+            value: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct enum identifier ignored_any
+    }
+}
+
+struct RecordAccess<'de> {
+    /// Key given to the visitor, Value name in the record, value
+    entries: std::vec::IntoIter<(&'de str, &'de String, &'de Value)>,
+    value: Option<(&'de String, &'de Value)>,
+}
+
+impl<'de> MapAccess<'de> for RecordAccess<'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeserializeError> {
+        match self.entries.next() {
+            Some((key, name, value)) => {
+                self.value = Some((name, value));
+                seed.deserialize(de::value::BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, DeserializeError> {
+        let (name, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before its key"))?;
+        seed.deserialize(ValueDeserializer::new(value))
+            .map_err(|mut e| {
+                if e.field.is_none() {
+                    e.field = Some(name.clone());
+                }
+                e
+            })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
@@ -0,0 +1,124 @@
+//! Find the template of an output whose command or platform is not known.
+
+use crate::{lenient, CliTable, TextFSM, Value};
+use log::warn;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+/// How well a template fits an output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateScore {
+    pub template: PathBuf,
+    /// Cells of the index row of the template
+    pub platform: Option<String>,
+    pub command: String,
+    /// Fraction of the input lines matched by a rule that is not a catch-all
+    pub line_coverage: f64,
+    pub records: usize,
+    /// Error transitions taken, the parse going on after them
+    pub error_transitions: usize,
+    /// Fraction of the Values that are set in at least one record
+    pub value_fill: f64,
+    /// `(0.7 * line_coverage + 0.3 * value_fill) / (1 + error_transitions)`,
+    /// 0 when the template emits no record
+    pub score: f64,
+}
+
+/// Score of a parse of an input.
+struct Fit {
+    line_coverage: f64,
+    records: usize,
+    error_transitions: usize,
+    value_fill: f64,
+}
+
+impl TextFSM {
+    fn fit(&mut self, input: &str) -> Fit {
+        let mut log = lenient::LenientOutput::default();
+        let mut lines = 0;
+        let mut specific = 0;
+        let _ = self.run_lines::<()>(input, Some(&mut log), |fsm, lines_read| {
+            // the EOF state runs with the number of the last line
+            if lines_read > lines {
+                lines = lines_read;
+                if fsm.line_matched_specific {
+                    specific += 1;
+                }
+            }
+            ControlFlow::Continue(())
+        });
+        let header = self.header();
+        let filled = header
+            .iter()
+            .filter(|name| {
+                self.records.iter().any(|rec| match rec.get(name) {
+                    Some(Value::Single(s)) => !s.is_empty(),
+                    Some(Value::List(l)) => !l.is_empty(),
+                    None => false,
+                })
+            })
+            .count();
+        Fit {
+            line_coverage: specific as f64 / lines.max(1) as f64,
+            records: self.records.len(),
+            error_transitions: log.diagnostics.len(),
+            value_fill: filled as f64 / header.len().max(1) as f64,
+        }
+    }
+}
+
+impl CliTable {
+    /// Run the templates of the index on the text, best fitting first.
+    ///
+    /// With a platform, only the rows whose Platform column matches it are tried.
+    /// The templates that can not be loaded are skipped.
+    pub fn detect_template(&self, text: &str, platform: Option<&str>) -> Vec<TemplateScore> {
+        let mut scores: Vec<TemplateScore> = vec![];
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        for rule in &self.regex_rules {
+            if let (Some(platform), Some(rx)) = (platform, rule.attribute_regexes.get("Platform")) {
+                if !rx.is_match(platform).unwrap_or(false) {
+                    continue;
+                }
+            }
+            let table = &self.tables[rule.table_index];
+            let row = &table.rows[rule.row_index];
+            let Some(dir) = Self::get_directory(&table.fname) else {
+                continue;
+            };
+            for template in &row.templates {
+                let path = PathBuf::from(&dir).join(template);
+                if !seen.insert(path.clone()) {
+                    continue;
+                }
+                let parser = match self.get_parser(&path) {
+                    Ok(parser) => parser,
+                    Err(e) => {
+                        warn!("{}: {}", path.display(), e);
+                        continue;
+                    }
+                };
+                let fit = TextFSM::from_parser(parser).fit(text);
+                let score = if fit.records == 0 {
+                    0.0
+                } else {
+                    (0.7 * fit.line_coverage + 0.3 * fit.value_fill)
+                        / (1 + fit.error_transitions) as f64
+                };
+                scores.push(TemplateScore {
+                    template: path,
+                    platform: row.platform.clone(),
+                    command: row.command.clone(),
+                    line_coverage: fit.line_coverage,
+                    records: fit.records,
+                    error_transitions: fit.error_transitions,
+                    value_fill: fit.value_fill,
+                    score,
+                });
+            }
+        }
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scores
+    }
+}
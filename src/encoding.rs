@@ -0,0 +1,99 @@
+//! Decoding of input that is not valid UTF-8, like the Latin-1 or the stray high
+//! bytes in the output of older switches and terminal servers.
+
+use crate::{DataRecord, DataRecordConversion, TextFSM, TextFSMError};
+use log::warn;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, the invalid sequences become U+FFFD
+    #[default]
+    Utf8Lossy,
+    /// UTF-8, the invalid sequences are read as Latin-1
+    Utf8OrLatin1,
+    /// ISO 8859-1, every byte is the code point of the same value
+    Latin1,
+}
+
+/// Bytes of the input that are not valid UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSequence {
+    /// Input line, numbered from 1
+    pub line: usize,
+    /// Byte offset in the input
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Decoded {
+    pub text: String,
+    /// Empty with Latin-1, where every byte is valid
+    pub invalid_sequences: Vec<InvalidSequence>,
+}
+
+/// Records of a parse of bytes, with where the input was not valid UTF-8.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedBytes {
+    pub records: Vec<DataRecord>,
+    pub invalid_sequences: Vec<InvalidSequence>,
+}
+
+fn push_latin1(text: &mut String, bytes: &[u8]) {
+    text.extend(bytes.iter().map(|&b| char::from(b)));
+}
+
+pub fn decode(input: &[u8], encoding: Encoding) -> Decoded {
+    let mut decoded = Decoded::default();
+    if encoding == Encoding::Latin1 {
+        push_latin1(&mut decoded.text, input);
+        return decoded;
+    }
+    let mut offset = 0;
+    let mut line = 1;
+    for chunk in input.utf8_chunks() {
+        let valid = chunk.valid();
+        decoded.text.push_str(valid);
+        offset += valid.len();
+        line += valid.matches('\n').count();
+        let invalid = chunk.invalid();
+        if invalid.is_empty() {
+            continue;
+        }
+        decoded.invalid_sequences.push(InvalidSequence {
+            line,
+            offset,
+            bytes: invalid.to_vec(),
+        });
+        match encoding {
+            Encoding::Utf8OrLatin1 => push_latin1(&mut decoded.text, invalid),
+            _ => decoded.text.push(char::REPLACEMENT_CHARACTER),
+        }
+        offset += invalid.len();
+    }
+    decoded
+}
+
+impl TextFSM {
+    /// Parse input bytes, decoded with the encoding. The invalid sequences are
+    /// logged, and returned with the records.
+    pub fn try_parse_bytes(
+        &mut self,
+        input: &[u8],
+        encoding: Encoding,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<ParsedBytes, TextFSMError> {
+        let decoded = decode(input, encoding);
+        for invalid in &decoded.invalid_sequences {
+            warn!(
+                "line {}: invalid UTF-8 {:02x?} at byte {}",
+                invalid.line, invalid.bytes, invalid.offset
+            );
+        }
+        let records = self.try_parse_string(&decoded.text, conversion)?;
+        Ok(ParsedBytes {
+            records,
+            invalid_sequences: decoded.invalid_sequences,
+        })
+    }
+}
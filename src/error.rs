@@ -0,0 +1,164 @@
+use crate::DataRecord;
+use std::fmt;
+use std::time::Duration;
+
+/// Failure of a parse, with the records produced before it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextFSMError {
+    /// The template reached an `Error` transition
+    ErrorState {
+        /// Message of the `Error "message"` action
+        message: Option<String>,
+        /// Input line, numbered from 1
+        line_number: usize,
+        line: String,
+        state: String,
+        records: Vec<DataRecord>,
+    },
+    /// The input file could not be read
+    Io { fname: String, message: String },
+    /// The input is larger than `ParseLimits::max_input_size`
+    InputTooLarge { size: usize, limit: usize },
+    /// An input line is longer than `ParseLimits::max_line_length`
+    LineTooLong {
+        length: usize,
+        limit: usize,
+        line_number: usize,
+        records: Vec<DataRecord>,
+    },
+    /// The parse emitted more than `ParseLimits::max_records` records
+    TooManyRecords {
+        limit: usize,
+        line_number: usize,
+        records: Vec<DataRecord>,
+    },
+    /// A fancy regex gave up on a line, past its backtrack limit
+    BacktrackLimitExceeded {
+        line_number: usize,
+        line: String,
+        state: String,
+        records: Vec<DataRecord>,
+    },
+    /// The parse took longer than `ParseLimits::timeout`
+    Timeout {
+        timeout: Duration,
+        line_number: usize,
+        records: Vec<DataRecord>,
+    },
+    /// The `CancelToken` of the parse was cancelled
+    Cancelled {
+        line_number: usize,
+        records: Vec<DataRecord>,
+    },
+}
+
+impl TextFSMError {
+    /// The records emitted before the failure.
+    pub fn records(&self) -> &[DataRecord] {
+        self.records_ref().map_or(&[], |records| records.as_slice())
+    }
+
+    pub fn into_records(mut self) -> Vec<DataRecord> {
+        self.records_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Replace the records carried by the error.
+    pub(crate) fn map_records(
+        mut self,
+        f: impl FnOnce(Vec<DataRecord>) -> Vec<DataRecord>,
+    ) -> Self {
+        if let Some(records) = self.records_mut() {
+            *records = f(std::mem::take(records));
+        }
+        self
+    }
+
+    fn records_ref(&self) -> Option<&Vec<DataRecord>> {
+        match self {
+            TextFSMError::ErrorState { records, .. }
+            | TextFSMError::LineTooLong { records, .. }
+            | TextFSMError::TooManyRecords { records, .. }
+            | TextFSMError::BacktrackLimitExceeded { records, .. }
+            | TextFSMError::Timeout { records, .. }
+            | TextFSMError::Cancelled { records, .. } => Some(records),
+            TextFSMError::Io { .. } | TextFSMError::InputTooLarge { .. } => None,
+        }
+    }
+
+    fn records_mut(&mut self) -> Option<&mut Vec<DataRecord>> {
+        match self {
+            TextFSMError::ErrorState { records, .. }
+            | TextFSMError::LineTooLong { records, .. }
+            | TextFSMError::TooManyRecords { records, .. }
+            | TextFSMError::BacktrackLimitExceeded { records, .. }
+            | TextFSMError::Timeout { records, .. }
+            | TextFSMError::Cancelled { records, .. } => Some(records),
+            TextFSMError::Io { .. } | TextFSMError::InputTooLarge { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for TextFSMError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextFSMError::ErrorState {
+                message,
+                line_number,
+                line,
+                state,
+                ..
+            } => {
+                write!(f, "Error state reached in state {}", state)?;
+                if let Some(message) = message {
+                    write!(f, ": {}", message)?;
+                }
+                write!(f, ". Input line {}: '{}'", line_number, line)
+            }
+            TextFSMError::Io { fname, message } => write!(f, "file {}: {}", fname, message),
+            TextFSMError::InputTooLarge { size, limit } => {
+                write!(f, "Input of {} bytes is over the limit of {}", size, limit)
+            }
+            TextFSMError::LineTooLong {
+                length,
+                limit,
+                line_number,
+                ..
+            } => write!(
+                f,
+                "Input line {} of {} bytes is over the limit of {}",
+                line_number, length, limit
+            ),
+            TextFSMError::TooManyRecords {
+                limit, line_number, ..
+            } => write!(
+                f,
+                "More than {} records at input line {}",
+                limit, line_number
+            ),
+            TextFSMError::BacktrackLimitExceeded {
+                line_number,
+                line,
+                state,
+                ..
+            } => write!(
+                f,
+                "Backtrack limit exceeded in state {}. Input line {}: '{}'",
+                state, line_number, line
+            ),
+            TextFSMError::Timeout {
+                timeout,
+                line_number,
+                ..
+            } => write!(
+                f,
+                "Parse timed out after {:?} at input line {}",
+                timeout, line_number
+            ),
+            TextFSMError::Cancelled { line_number, .. } => {
+                write!(f, "Parse cancelled at input line {}", line_number)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextFSMError {}
@@ -0,0 +1,34 @@
+//! Best-effort parsing: see `TextFSM::parse_string_lenient`.
+
+use crate::{DataRecord, TextFSMError};
+use serde::Serialize;
+
+/// An `Error` transition that the lenient mode went past.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    /// Input line, numbered from 1
+    pub line_number: usize,
+    pub line: String,
+    pub state: String,
+    /// Message of the `Error "message"` action
+    pub message: Option<String>,
+}
+
+/// An input line that no rule of the state matched.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnmatchedLine {
+    /// Input line, numbered from 1
+    pub line_number: usize,
+    pub line: String,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LenientOutput {
+    pub records: Vec<DataRecord>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub unmatched_lines: Vec<UnmatchedLine>,
+    /// Set when a limit of the parse stopped it, `records` holds what was
+    /// parsed before
+    pub error: Option<TextFSMError>,
+}
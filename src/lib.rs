@@ -1,21 +1,55 @@
-use log::{debug, error, info, log_enabled, trace, Level};
+use log::{debug, error, info, log_enabled, trace, warn, Level};
 pub use pest::iterators::Pair;
 pub use pest::Parser;
 use pest_derive::Parser;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::Arc;
 
 pub mod cli_table;
+pub mod conversion;
+pub mod de;
+pub mod detect;
+pub mod encoding;
+pub mod error;
+pub mod lenient;
+pub mod limits;
+pub mod metadata;
+pub mod output;
+pub mod pipeline;
+pub mod preprocess;
+pub mod provenance;
+pub mod schema;
+pub mod session;
+pub mod template_cache;
+pub mod template_fmt;
+pub mod texttable;
+pub mod typed;
 pub mod varsubst;
 pub use cli_table::CliTable;
+pub use conversion::KeyConverter;
+pub use error::TextFSMError;
+pub use template_cache::TemplateCache;
+pub use texttable::TextTable;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataRecord {
     #[serde(flatten)]
     pub fields: HashMap<String, Value>,
     #[serde(skip_deserializing)]
     pub record_key: Option<String>,
+    /// Set when the parse session tracks the provenance
+    #[serde(skip)]
+    pub provenance: Option<provenance::RecordProvenance>,
+}
+
+/// Records are equal when their values are, wherever they come from.
+impl PartialEq for DataRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields == other.fields && self.record_key == other.record_key
+    }
 }
 
 impl DataRecord {
@@ -125,6 +159,9 @@ impl DataRecord {
 
     pub fn remove(&mut self, key: &str) {
         self.fields.remove(key);
+        if let Some(ref mut provenance) = self.provenance {
+            provenance.values.remove(key);
+        }
     }
     pub fn keys(&self) -> std::collections::hash_map::Keys<'_, String, Value> {
         self.fields.keys()
@@ -143,6 +180,7 @@ impl Default for DataRecord {
         DataRecord {
             fields: Default::default(),
             record_key: None,
+            provenance: None,
         }
     }
 }
@@ -158,17 +196,43 @@ pub enum Value {
 #[grammar = "textfsm.pest"]
 pub struct TextFSMParser {
     pub values: HashMap<String, ValueDefinition>,
+    pub value_names: Vec<String>,
     pub mandatory_values: Vec<String>,
     pub states: HashMap<String, StateCompiled>,
+    /// State names in the order of their definition in the template
+    pub state_names: Vec<String>,
+    /// Types given by the `# @type` directives
+    pub value_types: HashMap<String, typed::ValueType>,
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct TextFSM {
-    pub parser: TextFSMParser,
+    /// Shared with the template cache and the other sessions of the template
+    pub parser: Arc<TextFSMParser>,
     pub curr_state: String,
     pub curr_record: DataRecord,
     pub filldown_record: DataRecord,
     pub records: Vec<DataRecord>,
+    /// Input line where each of the records was emitted
+    pub record_lines: Vec<usize>,
+    /// Post-processing of the records once the parse is done
+    pub pipeline: pipeline::Pipeline,
+    /// Clean-up of the input before it is parsed
+    pub preprocessor: preprocess::Preprocessor,
+    /// Metadata columns set in every record, conflicts resolved
+    pub(crate) metadata: Vec<(String, String)>,
+    /// Record where the records and values come from
+    pub track_provenance: bool,
+    /// Number of the input line being parsed
+    pub current_line: usize,
+    /// Whether a rule of the state matched the last line
+    pub(crate) line_matched: bool,
+    /// Whether a rule other than a catch-all matched the last line
+    pub(crate) line_matched_specific: bool,
+    pub limits: limits::ParseLimits,
+    pub cancel: Option<limits::CancelToken>,
+    /// Set by `parse_line` when a fancy regex gave up on the line
+    pub(crate) backtrack_limit_exceeded: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -228,6 +292,8 @@ pub enum MultiRegex {
     Fancy(fancy_regex::Regex),
 }
 
+const CATCH_ALL_PROBE: &str = "\u{7f}\u{7f}";
+
 #[derive(Debug, Clone)]
 pub struct StateRuleCompiled {
     _rule_match: String,
@@ -235,6 +301,8 @@ pub struct StateRuleCompiled {
     match_variables: Vec<String>,
     maybe_regex: Option<MultiRegex>,
     transition: RuleTransition,
+    /// Matches any line without capturing a value, like `^.*`
+    catch_all: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -243,9 +311,27 @@ pub struct StateCompiled {
     rules: Vec<StateRuleCompiled>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedOutput {
+    /// Value names in the order of their declaration in the template(s)
+    pub header: Vec<String>,
+    pub records: Vec<DataRecord>,
+}
+
+/// Conversion of the Value names used as the keys of the records.
+#[derive(Clone)]
 pub enum DataRecordConversion {
     LowercaseKeys,
+    UppercaseKeys,
+    /// `InterfaceName` and `INTERFACE_NAME` both become `interface_name`
+    SnakeCaseKeys,
+    /// Rename the listed keys, the others are kept
+    RenameKeys(HashMap<String, String>),
+    /// Prepend a string, like the name of the command or the template
+    PrefixKeys(String),
+    Custom(Arc<dyn KeyConverter>),
+    /// Apply the conversions in order
+    Chain(Vec<DataRecordConversion>),
 }
 
 impl TextFSMParser {
@@ -332,27 +418,27 @@ impl TextFSMParser {
                     // println!("TRANSITION: {:?}", &transition);
                 }
                 x => {
-                    println!("{}state Rule:    {:?}", spaces, pair.as_rule());
-                    println!("{}Span:    {:?}", spaces, pair.as_span());
-                    println!("{}Text:    {}", spaces, pair.as_str());
+                    error!("{}state Rule:    {:?}", spaces, pair.as_rule());
+                    error!("{}Span:    {:?}", spaces, pair.as_span());
+                    error!("{}Text:    {}", spaces, pair.as_str());
                     panic!("state rule {:?} not supported", &x);
                 }
             }
         }
         let mut rule_match = rule_match.expect("rule_match must be always set");
         if (rule_match.ends_with(" ") || rule_match.ends_with("\t")) && !has_action {
-            println!(
-                "WARNING: '{}' has trailing spaces without transition action!",
+            warn!(
+                "'{}' has trailing spaces without transition action!",
                 &rule_match
             );
             rule_match = rule_match.trim_end().to_string();
         }
         if rule_match.contains(r#"\<"#) {
-            println!("WARNING: replacing \\< with < in '{}'", &rule_match);
+            warn!("replacing \\< with < in '{}'", &rule_match);
             rule_match = rule_match.replace("\\<", "<");
         }
         if rule_match.contains(r#"\>"#) {
-            println!("WARNING: replacing \\> with > in '{}'", &rule_match);
+            warn!("replacing \\> with > in '{}'", &rule_match);
             rule_match = rule_match.replace("\\>", ">");
         }
         StateRule {
@@ -368,7 +454,8 @@ impl TextFSMParser {
         let mut expanded_rule_match: String = format!("");
         let rule_match = rule.rule_match.clone();
         let mut match_variables: Vec<String> = vec![];
-        let varsubst = varsubst::VariableParser::parse_dollar_string(&rule_match).unwrap();
+        let varsubst = varsubst::VariableParser::parse_dollar_string(&rule_match)
+            .map_err(|e| format!("Can not parse rule_match '{}': {}", &rule_match, e))?;
         // println!("DOLLAR STR: {:?}", &varsubst);
         {
             use varsubst::ParseChunk;
@@ -382,10 +469,12 @@ impl TextFSMParser {
                             expanded_rule_match.push_str(&v_out);
                             match_variables.push(v.to_string());
                         }
-                        None => panic!(
-                            "Can not find variable '{}' while parsing rule_match '{}'",
-                            &v, &rule.rule_match
-                        ),
+                        None => {
+                            return Err(format!(
+                                "Can not find variable '{}' while parsing rule_match '{}'",
+                                &v, &rule.rule_match
+                            ))
+                        }
                     },
                 }
             }
@@ -405,32 +494,47 @@ impl TextFSMParser {
                             break x;
                         }
                         Err(Error::ParseError(pos, e)) => {
-                            println!("STR:{}", &expanded_rule_match[0..pos + 1]);
-                            println!("ERR:{}^", " ".repeat(pos));
+                            debug!("STR:{}", &expanded_rule_match[0..pos + 1]);
+                            debug!("ERR:{}^", " ".repeat(pos));
                             match e {
                                 ParseError::TargetNotRepeatable => {
                                     if let Some(char_index) =
                                         expanded_rule_match.char_indices().nth(pos)
                                     {
-                                        println!("WARNING: repeat quantifier on a lookahead, lookbehind or other zero-width item");
+                                        warn!("repeat quantifier on a lookahead, lookbehind or other zero-width item");
                                         expanded_rule_match.remove(char_index.0);
                                     } else {
-                                        panic!("Can not fix up regex!");
+                                        return Err(format!(
+                                            "Can not fix up regex '{}'",
+                                            &expanded_rule_match
+                                        ));
                                     }
                                 }
                                 e => {
-                                    panic!("Error: {:?}", &e);
+                                    return Err(format!(
+                                        "Error: {:?} in regex '{}'",
+                                        &e, &expanded_rule_match
+                                    ));
                                 }
                             }
                         }
-                        x => {
-                            panic!("Error: {:?}", &x);
+                        Err(e) => {
+                            return Err(format!(
+                                "Error: {:?} in regex '{}'",
+                                &e, &expanded_rule_match
+                            ));
                         }
                     }
                 };
                 MultiRegex::Fancy(freg)
             }
         };
+        // a line no template would contain
+        let catch_all = match_variables.is_empty()
+            && match regex_val {
+                MultiRegex::Classic(ref rx) => rx.is_match(CATCH_ALL_PROBE),
+                MultiRegex::Fancy(ref rx) => rx.is_match(CATCH_ALL_PROBE).unwrap_or(false),
+            };
         let maybe_regex = Some(regex_val);
         let transition = rule.transition.clone();
         let _rule_match = rule_match;
@@ -442,6 +546,7 @@ impl TextFSMParser {
             match_variables,
             maybe_regex,
             transition,
+            catch_all,
         })
     }
     pub fn parse_and_compile_state_definition(
@@ -462,15 +567,15 @@ impl TextFSMParser {
                     for pair in pair.clone().into_inner() {
                         let rule = Self::parse_state_rule(&pair);
                         trace!("PARSED RULE [{:?}]: {:#?}", &name, &rule);
-                        let compiled_rule = Self::compile_state_rule(&rule, values).unwrap();
+                        let compiled_rule = Self::compile_state_rule(&rule, values)?;
                         rules.push(compiled_rule);
                     }
                 }
                 x => {
                     let spaces = "";
-                    println!("{}state def Rule:    {:?}", spaces, pair.as_rule());
-                    println!("{}Span:    {:?}", spaces, pair.as_span());
-                    println!("{}Text:    {}", spaces, pair.as_str());
+                    error!("{}state def Rule:    {:?}", spaces, pair.as_rule());
+                    error!("{}Span:    {:?}", spaces, pair.as_span());
+                    error!("{}Text:    {}", spaces, pair.as_str());
                     panic!("Rule not supported in state definition: {:?}", &x);
                 }
             }
@@ -535,11 +640,11 @@ impl TextFSMParser {
                 }
             }
             if regex_pattern.contains(r#"\<"#) {
-                println!("WARNING: replacing \\< with < in value '{}'", &name);
+                warn!("replacing \\< with < in value '{}'", &name);
                 regex_pattern = regex_pattern.replace("\\<", "<");
             }
             if regex_pattern.contains(r#"\>"#) {
-                println!("WARNING: replacing \\> with > in value '{}'", &name);
+                warn!("replacing \\> with > in value '{}'", &name);
                 regex_pattern = regex_pattern.replace("\\>", ">");
             }
             Ok(ValueDefinition {
@@ -559,10 +664,12 @@ impl TextFSMParser {
             ))
         }
     }
+    #[allow(clippy::type_complexity)]
     pub fn parse_value_defs(
         pair: &Pair<'_, Rule>,
-    ) -> Result<(HashMap<String, ValueDefinition>, Vec<String>), String> {
+    ) -> Result<(HashMap<String, ValueDefinition>, Vec<String>, Vec<String>), String> {
         let mut vals = HashMap::new();
+        let mut value_names: Vec<String> = vec![];
         let mut mandatory_values: Vec<String> = vec![];
         for pair in pair.clone().into_inner() {
             if Rule::value_definition == pair.as_rule() {
@@ -570,20 +677,33 @@ impl TextFSMParser {
                 if val.is_required {
                     mandatory_values.push(val.name.clone());
                 }
+                if !value_names.contains(&val.name) {
+                    value_names.push(val.name.clone());
+                }
                 vals.insert(val.name.clone(), val);
             }
         }
-        Ok((vals, mandatory_values))
+        Ok((vals, value_names, mandatory_values))
     }
     pub fn from_file(fname: &str) -> Self {
         // println!("Path: {}", &fname);
-        let template = std::fs::read_to_string(&fname).expect("File read failed");
+        let template = std::fs::read_to_string(fname).expect("File read failed");
+        match Self::from_string(&template) {
+            Ok(parser) => parser,
+            Err(e) => panic!("file {} Error: {}", &fname, e),
+        }
+    }
+
+    /// Compile a template from its text, reporting problems instead of panicking.
+    pub fn from_string(template: &str) -> Result<Self, String> {
         // pad with a newline, because dealing with a missing one within grammar is a PITA
         let template = format!("{}\n\n\n", template);
 
         let mut seen_eoi = false;
         let mut values: HashMap<String, ValueDefinition> = HashMap::new();
+        let mut value_names: Vec<String> = vec![];
         let mut states: HashMap<String, StateCompiled> = HashMap::new();
+        let mut state_names: Vec<String> = vec![];
         let mut mandatory_values: Vec<String> = vec![];
 
         let end_state = NextState::NamedState(format!("End"));
@@ -595,7 +715,7 @@ impl TextFSMParser {
             },
         };
 
-        let compiled_eof_rule = Self::compile_state_rule(&eof_rule, &values).unwrap();
+        let compiled_eof_rule = Self::compile_state_rule(&eof_rule, &values)?;
 
         let eof_state = StateCompiled {
             name: format!("EOF"),
@@ -608,7 +728,8 @@ impl TextFSMParser {
                 for pair in pairs.clone() {
                     match pair.as_rule() {
                         Rule::value_definitions => {
-                            (values, mandatory_values) = Self::parse_value_defs(&pair).unwrap();
+                            (values, value_names, mandatory_values) =
+                                Self::parse_value_defs(&pair)?;
                         }
                         Rule::state_definitions => {
                             for pair in pair.clone().into_inner() {
@@ -618,16 +739,16 @@ impl TextFSMParser {
                                         Self::_log_pair(0, &pair);
                                         let state = Self::parse_and_compile_state_definition(
                                             &pair, &values,
-                                        )
-                                        .unwrap();
+                                        )?;
                                         trace!("STATE DEFINITION END: {:?}", &state);
-                                        if &state.name != "EOF" {
-                                            if states.get(&state.name).is_some() {
-                                                panic!(
-                                                    "State {} already defined in the file!",
-                                                    &state.name
-                                                );
-                                            }
+                                        if state.name != "EOF" && states.contains_key(&state.name) {
+                                            return Err(format!(
+                                                "State {} already defined in the file!",
+                                                &state.name
+                                            ));
+                                        }
+                                        if !state_names.contains(&state.name) {
+                                            state_names.push(state.name.clone());
                                         }
                                         states.insert(state.name.clone(), state);
                                     }
@@ -648,32 +769,175 @@ impl TextFSMParser {
                 }
 
                 if !seen_eoi {
-                    println!("WARNING: EOI token not seen");
+                    warn!("EOI token not seen");
                 }
 
-                // FIXME: check that the "Start" state exists
-                return TextFSMParser {
+                let mut value_types = HashMap::new();
+                for (name, typ) in typed::parse_type_directives(&template)? {
+                    if !values.contains_key(&name) {
+                        return Err(format!("type directive for unknown value '{}'", name));
+                    }
+                    value_types.insert(name, typ);
+                }
+
+                if !states.contains_key("Start") {
+                    return Err("Missing state 'Start'".to_string());
+                }
+                for state_name in &state_names {
+                    for rule in &states[state_name].rules {
+                        if let LineAction::Next(Some(NextState::NamedState(ref next))) =
+                            rule.transition.line_action
+                        {
+                            if next != "End" && !states.contains_key(next) {
+                                return Err(format!(
+                                    "State '{}' not found, used in state '{}'",
+                                    next, state_name
+                                ));
+                            }
+                        }
+                    }
+                }
+                Ok(TextFSMParser {
                     values,
+                    value_names,
                     mandatory_values,
                     states,
+                    state_names,
+                    value_types,
+                })
+            }
+            Err(e) => Err(format!("{}", e)),
+        }
+    }
+
+    /// Render the state machine as a Graphviz digraph.
+    ///
+    /// The edges are the rules with an explicit next state, labelled with the
+    /// rule match and the record action, if any.
+    pub fn to_dot(&self) -> String {
+        fn quote(s: &str) -> String {
+            format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        let mut out = String::from("digraph textfsm {\n");
+        let mut targets: Vec<String> = vec![];
+        for name in &self.state_names {
+            out.push_str(&format!("  {};\n", quote(name)));
+        }
+        for name in &self.state_names {
+            let state = &self.states[name];
+            for rule in &state.rules {
+                let target = match &rule.transition.line_action {
+                    LineAction::Next(Some(NextState::NamedState(next))) => next.clone(),
+                    LineAction::Next(Some(NextState::Error(_))) => "Error".to_string(),
+                    _ => continue,
                 };
+                let mut label = rule._rule_match.clone();
+                if rule.transition.record_action != RecordAction::NoRecord {
+                    label = format!("{} [{:?}]", label, rule.transition.record_action);
+                }
+                if let LineAction::Next(Some(NextState::Error(Some(msg)))) =
+                    &rule.transition.line_action
+                {
+                    label = format!("{} {}", label, msg);
+                }
+                out.push_str(&format!(
+                    "  {} -> {} [label={}];\n",
+                    quote(name),
+                    quote(&target),
+                    quote(&label)
+                ));
+                if !self.state_names.contains(&target) && !targets.contains(&target) {
+                    targets.push(target);
+                }
             }
-            Err(e) => panic!("file {} Error: {}", &fname, e),
         }
+        for target in &targets {
+            let shape = if target == "Error" {
+                "octagon"
+            } else {
+                "doublecircle"
+            };
+            out.push_str(&format!("  {} [shape={}];\n", quote(target), shape));
+        }
+        out.push_str("}\n");
+        out
     }
 }
 
 impl TextFSM {
     pub fn from_file(fname: &str) -> Self {
         let parser = TextFSMParser::from_file(fname);
-        let curr_state = format!("Start");
+        Self::from_parser(parser)
+    }
+
+    /// Start a fresh parse session with an already compiled template, owned or
+    /// shared.
+    pub fn from_parser(parser: impl Into<Arc<TextFSMParser>>) -> Self {
+        let curr_state = "Start".to_string();
         TextFSM {
-            parser,
+            parser: parser.into(),
             curr_state,
             ..Default::default()
         }
     }
 
+    /// Post-process the records with the pipeline once the parse is done, so that
+    /// it also sees the values set by Fillup.
+    pub fn with_pipeline(mut self, pipeline: pipeline::Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Clean up the input with the filters of the preprocessor before parsing it.
+    pub fn with_preprocessor(mut self, preprocessor: preprocess::Preprocessor) -> Self {
+        self.preprocessor = preprocessor;
+        self
+    }
+
+    /// Set the metadata columns in every record, and in front of the header.
+    pub fn with_metadata(mut self, metadata: metadata::Metadata) -> Result<Self, String> {
+        self.metadata = metadata.resolve(&self.parser.value_names)?;
+        Ok(self)
+    }
+
+    /// Record the input lines, states and rules the records and values come from.
+    pub fn with_provenance(mut self, track: bool) -> Self {
+        self.track_provenance = track;
+        self
+    }
+
+    /// Bound the resources of the parse, the fancy regexes are recompiled when
+    /// the limits set a backtrack limit, on a copy of a shared template.
+    pub fn with_limits(mut self, limits: limits::ParseLimits) -> Result<Self, String> {
+        if let Some(backtrack_limit) = limits.backtrack_limit {
+            Arc::make_mut(&mut self.parser).set_backtrack_limit(backtrack_limit)?;
+        }
+        self.limits = limits;
+        Ok(self)
+    }
+
+    /// Stop the parse at the next line once the token is cancelled.
+    pub fn with_cancel_token(mut self, token: limits::CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Value names in the order of their declaration in the template, after the
+    /// metadata columns.
+    pub fn header(&self) -> Vec<String> {
+        metadata::header_with(&self.metadata, &self.parser.value_names)
+    }
+
+    /// Names of the Key values, in the order of their declaration.
+    pub fn key_names(&self) -> Vec<String> {
+        self.parser
+            .value_names
+            .iter()
+            .filter(|name| self.is_key_value(name) == Some(true))
+            .cloned()
+            .collect()
+    }
+
     pub fn set_curr_state(&mut self, state_name: &str) {
         if state_name != "End" {
             if self.parser.states.get(state_name).is_none() {
@@ -760,14 +1024,37 @@ impl TextFSM {
         }
     }
 
+    fn capture_provenance(
+        &self,
+        curr_record: &mut DataRecord,
+        filldown_record: &mut DataRecord,
+        name: &str,
+        span: std::ops::Range<usize>,
+        state: &str,
+        rule_index: usize,
+    ) {
+        let capture = provenance::ValueProvenance {
+            line: self.current_line,
+            span,
+            state: state.to_string(),
+            rule_index,
+        };
+        if self.is_filldown_value(name).unwrap() {
+            filldown_record.set_value_provenance(name, capture.clone());
+        }
+        curr_record.set_value_provenance(name, capture);
+    }
+
     pub fn parse_line(&mut self, aline: &str) -> Option<NextState> {
         let maybe_next_state: Option<NextState> = None;
+        self.line_matched = false;
+        self.line_matched_specific = false;
 
         let curr_state = self.curr_state.clone();
 
         if let Some(ref curr_state) = self.parser.states.get(&curr_state) {
             trace!("CURR STATE: {:?}", &curr_state);
-            for rule in &curr_state.rules {
+            for (rule_index, rule) in curr_state.rules.iter().enumerate() {
                 let mut transition: RuleTransition = Default::default();
                 transition.line_action = LineAction::Continue;
                 trace!("TRY RULE: {:?}", &rule);
@@ -788,6 +1075,16 @@ impl TextFSM {
                                     maybe_value,
                                     aline,
                                 );
+                                if let (true, Some(m)) = (self.track_provenance, caps.name(name)) {
+                                    self.capture_provenance(
+                                        &mut tmp_datarec,
+                                        &mut tmp_filldown_rec,
+                                        name,
+                                        m.start()..m.end(),
+                                        &curr_state.name,
+                                        rule_index,
+                                    );
+                                }
                             }
                             capture_matched = true;
                         }
@@ -795,20 +1092,34 @@ impl TextFSM {
                     Some(MultiRegex::Fancy(rx)) => {
                         debug!("RULE(FANCY REGEX): {:?}", &rule);
                         for caps in rx.captures_iter(aline) {
+                            let caps = match caps {
+                                Ok(caps) => caps,
+                                Err(e) => {
+                                    // backtrack limit or stack overflow, the caller reports it
+                                    warn!("FANCY regex gave up: {}", e);
+                                    self.backtrack_limit_exceeded = true;
+                                    return None;
+                                }
+                            };
                             for name in &rule.match_variables {
-                                if let Ok(ref caps) = caps {
-                                    let maybe_value =
-                                        caps.name(name).map(|x| x.as_str().to_string());
-                                    self.insert_value(
-                                        "FANCY",
+                                let maybe_value = caps.name(name).map(|x| x.as_str().to_string());
+                                self.insert_value(
+                                    "FANCY",
+                                    &mut tmp_datarec,
+                                    &mut tmp_filldown_rec,
+                                    name,
+                                    maybe_value,
+                                    aline,
+                                );
+                                if let (true, Some(m)) = (self.track_provenance, caps.name(name)) {
+                                    self.capture_provenance(
                                         &mut tmp_datarec,
                                         &mut tmp_filldown_rec,
                                         name,
-                                        maybe_value,
-                                        aline,
+                                        m.start()..m.end(),
+                                        &curr_state.name,
+                                        rule_index,
                                     );
-                                } else {
-                                    panic!("FANCY caps not ok");
                                 }
                             }
                             capture_matched = true;
@@ -819,9 +1130,16 @@ impl TextFSM {
                     }
                 }
                 if capture_matched {
+                    self.line_matched = true;
+                    self.line_matched_specific |= !rule.catch_all;
                     trace!("TMP_REC: {:?}", &tmp_datarec);
                     trace!("TMP_FILLDOWN: {:?}", &tmp_filldown_rec);
+                    let tmp_provenance = tmp_datarec.provenance.take();
                     for (name, v) in tmp_datarec.fields {
+                        let captures = tmp_provenance
+                            .as_ref()
+                            .and_then(|p| p.value(&name))
+                            .map(|c| c.to_vec());
                         if self.is_fillup_value(&name).unwrap() {
                             let name = &name;
                             for fillup_record in self.records.iter_mut().rev() {
@@ -838,17 +1156,29 @@ impl TextFSM {
                                     }
                                 }
                                 fillup_record.fields.insert(name.to_string(), v.clone());
+                                if let Some(ref captures) = captures {
+                                    fillup_record.append_value_provenance(name, captures);
+                                }
                             }
                         }
-                        self.curr_record.append_value(name, v);
+                        self.curr_record.append_value(name.clone(), v);
+                        if let Some(ref captures) = captures {
+                            self.curr_record.append_value_provenance(&name, captures);
+                        }
                     }
                     trace!("TMP KEY: {:?}", &tmp_datarec.record_key);
                     self.curr_record.record_key = tmp_datarec.record_key;
                     // The below is incorrect:
                     // self.filldown_record.overwrite_from(tmp_filldown_rec);
                     // This is correct:
+                    let tmp_provenance = tmp_filldown_rec.provenance.take();
                     for (name, v) in tmp_filldown_rec.fields {
-                        self.filldown_record.append_value(name, v);
+                        self.filldown_record.append_value(name.clone(), v);
+                        if let Some(captures) = tmp_provenance.as_ref().and_then(|p| p.value(&name))
+                        {
+                            self.filldown_record
+                                .append_value_provenance(&name, captures);
+                        }
                     }
                     transition = rule.transition.clone();
                 }
@@ -886,6 +1216,26 @@ impl TextFSM {
                                         }
                                     }
                                 }
+                                if self.track_provenance {
+                                    let provenance =
+                                        new_rec.provenance.get_or_insert_with(Default::default);
+                                    provenance.last_line = self.current_line;
+                                    provenance.first_line = provenance
+                                        .values
+                                        .values()
+                                        .flatten()
+                                        .map(|capture| capture.line)
+                                        .min()
+                                        .unwrap_or(self.current_line);
+                                }
+                                for (name, value) in &self.metadata {
+                                    new_rec
+                                        .fields
+                                        .insert(name.clone(), Value::Single(value.clone()));
+                                    if let Some(ref mut provenance) = new_rec.provenance {
+                                        provenance.values.remove(name);
+                                    }
+                                }
                                 trace!("RECORD: {:?}", &new_rec);
                                 self.records.push(new_rec);
                             } else {
@@ -924,19 +1274,8 @@ impl TextFSM {
         maybe_next_state
     }
 
-    pub fn lowercase_keys(src: &Vec<DataRecord>) -> Vec<DataRecord> {
-        let mut out = vec![];
-
-        for irec in src {
-            let mut hm = DataRecord::new();
-            hm.record_key = irec.record_key.clone();
-            for (k, v) in irec.iter() {
-                let kl = k.to_lowercase();
-                hm.fields.insert(kl, v.clone());
-            }
-            out.push(hm);
-        }
-        out
+    pub fn lowercase_keys(src: &[DataRecord]) -> Vec<DataRecord> {
+        DataRecordConversion::LowercaseKeys.convert_records(src)
     }
 
     pub fn parse_file(
@@ -944,13 +1283,115 @@ impl TextFSM {
         fname: &str,
         conversion: Option<DataRecordConversion>,
     ) -> Vec<DataRecord> {
-        let input = std::fs::read_to_string(&fname).expect("Data file read failed");
+        let input = std::fs::read_to_string(fname).expect("Data file read failed");
+        self.parse_string(&input, conversion)
+    }
+
+    /// Run the input through the state machine, calling `after_line` with the
+    /// number of the line once the records it emitted are in `self.records`.
+    ///
+    /// The input goes through the preprocessor first.
+    ///
+    /// With a lenient log, the Error transitions and the unmatched lines are
+    /// logged and the parse goes on in the current state.
+    ///
+    /// The pipeline is applied to the records of this input once it is parsed,
+    /// also to the ones of an error.
+    fn run_lines<B>(
+        &mut self,
+        input: &str,
+        lenient: Option<&mut lenient::LenientOutput>,
+        after_line: impl FnMut(&mut Self, usize) -> ControlFlow<B>,
+    ) -> Result<ControlFlow<B>, TextFSMError> {
+        let first_record = self.records.len();
+        let result = self.run_state_machine(input, lenient, after_line);
+        if self.pipeline.is_empty() {
+            return result;
+        }
+        let records = self.records.split_off(first_record.min(self.records.len()));
+        let lines = self
+            .record_lines
+            .split_off(first_record.min(self.record_lines.len()));
+        for (i, rec) in records.into_iter().enumerate() {
+            match self.pipeline.apply(rec) {
+                Some(rec) => {
+                    self.records.push(rec);
+                    self.record_lines.extend(lines.get(i));
+                }
+                None => trace!("RECORD: dropped by the pipeline"),
+            }
+        }
+        result.map_err(|e| {
+            e.map_records(|mut records| {
+                let new_records = records.split_off(first_record.min(records.len()));
+                records.extend(
+                    new_records
+                        .into_iter()
+                        .filter_map(|rec| self.pipeline.apply(rec)),
+                );
+                records
+            })
+        })
+    }
+
+    fn run_state_machine<B>(
+        &mut self,
+        input: &str,
+        mut lenient: Option<&mut lenient::LenientOutput>,
+        mut after_line: impl FnMut(&mut Self, usize) -> ControlFlow<B>,
+    ) -> Result<ControlFlow<B>, TextFSMError> {
+        if let Some(limit) = self.limits.max_input_size {
+            if input.len() > limit {
+                return Err(TextFSMError::InputTooLarge {
+                    size: input.len(),
+                    limit,
+                });
+            }
+        }
+        let input = self.preprocessor.apply(input);
+        let started = std::time::Instant::now();
+        let mut emitted = 0;
+        let mut lines_read = 0;
         for (lineno, aline) in input.lines().enumerate() {
             debug!("LINE:#{}:'{}'", lineno + 1, &aline);
-            if let Some(next_state) = self.parse_line(&aline) {
+            lines_read = lineno + 1;
+            self.current_line = lines_read;
+            self.check_line_limits(started, lines_read, aline)?;
+            let records_before = self.records.len();
+            let next_state = self.parse_line(aline);
+            emitted += self.records.len() - records_before;
+            self.check_parsed_line(emitted, lines_read, aline)?;
+            if let ControlFlow::Break(b) = after_line(self, lines_read) {
+                return Ok(ControlFlow::Break(b));
+            }
+            if let (false, Some(ref mut log)) = (self.line_matched, &mut lenient) {
+                log.unmatched_lines.push(lenient::UnmatchedLine {
+                    line_number: lines_read,
+                    line: aline.to_string(),
+                    state: self.curr_state.clone(),
+                });
+            }
+            if let Some(next_state) = next_state {
                 match next_state {
                     NextState::Error(maybe_msg) => {
-                        panic!("Error state reached! msg: {:?}", &maybe_msg);
+                        let message = maybe_msg.map(|msg| msg.trim_matches('"').to_string());
+                        if let Some(ref mut log) = lenient {
+                            warn!("line {}: Error state reached, going on", lines_read);
+                            log.diagnostics.push(lenient::Diagnostic {
+                                line_number: lines_read,
+                                line: aline.to_string(),
+                                state: self.curr_state.clone(),
+                                message,
+                            });
+                            continue;
+                        }
+                        return Err(TextFSMError::ErrorState {
+                            message,
+                            line_number: lines_read,
+                            line: aline.to_string(),
+                            state: self.curr_state.clone(),
+                            records: self.records.clone(),
+                        });
                     }
                     NextState::NamedState(name) => {
                         self.set_curr_state(&name);
@@ -963,13 +1404,136 @@ impl TextFSM {
         }
         if &self.curr_state != "End" {
             self.set_curr_state("EOF");
+            let records_before = self.records.len();
             self.parse_line("");
+            emitted += self.records.len() - records_before;
+            self.check_parsed_line(emitted, lines_read, "")?;
+            if let ControlFlow::Break(b) = after_line(self, lines_read) {
+                return Ok(ControlFlow::Break(b));
+            }
             // FIXME: Can EOF state transition into something else ? Presumably not.
             self.set_curr_state("End");
         }
-        match conversion {
-            None => self.records.clone(),
-            Some(DataRecordConversion::LowercaseKeys) => Self::lowercase_keys(&self.records),
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Parse the input, panicking when the template reaches the Error state.
+    pub fn parse_string(
+        &mut self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Vec<DataRecord> {
+        self.try_parse_string(input, conversion)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parse the input, returning an error with the records produced so far when
+    /// the template reaches the Error state.
+    pub fn try_parse_string(
+        &mut self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Vec<DataRecord>, TextFSMError> {
+        let result = self.run_lines::<()>(input, None, |fsm, lines_read| {
+            fsm.record_lines.resize(fsm.records.len(), lines_read);
+            ControlFlow::Continue(())
+        });
+        let convert = |records: Vec<DataRecord>| match conversion {
+            None => records,
+            Some(ref conversion) => conversion.convert_records(&records),
+        };
+        match result {
+            Ok(_) => Ok(convert(self.records.clone())),
+            Err(e) => Err(e.map_records(convert)),
         }
     }
+
+    /// Parse the input best-effort: the Error transitions become diagnostics and
+    /// the parse goes on in the current state, and the lines no rule matched are
+    /// collected.
+    pub fn parse_string_lenient(
+        &mut self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> lenient::LenientOutput {
+        let mut out = lenient::LenientOutput::default();
+        let result = self.run_lines::<()>(input, Some(&mut out), |fsm, lines_read| {
+            fsm.record_lines.resize(fsm.records.len(), lines_read);
+            ControlFlow::Continue(())
+        });
+        let records = match result {
+            Ok(_) => self.records.clone(),
+            Err(e) => {
+                let records = e.records().to_vec();
+                out.error = Some(e.map_records(|_| vec![]));
+                records
+            }
+        };
+        out.records = match conversion {
+            None => records,
+            Some(conversion) => conversion.convert_records(&records),
+        };
+        out
+    }
+
+    pub fn try_parse_file(
+        &mut self,
+        fname: &str,
+        conversion: Option<DataRecordConversion>,
+    ) -> Result<Vec<DataRecord>, TextFSMError> {
+        let io_error = |e: std::io::Error| TextFSMError::Io {
+            fname: fname.to_string(),
+            message: e.to_string(),
+        };
+        if let Some(limit) = self.limits.max_input_size {
+            // do not read a file that is too large
+            let size = std::fs::metadata(fname).map_err(io_error)?.len() as usize;
+            if size > limit {
+                return Err(TextFSMError::InputTooLarge { size, limit });
+            }
+        }
+        let input = std::fs::read_to_string(fname).map_err(io_error)?;
+        self.try_parse_string(&input, conversion)
+    }
+
+    /// Parse the input, handing each record to the sink as soon as it is emitted
+    /// instead of accumulating it in `records`.
+    ///
+    /// The parse stops early when the sink breaks, and the break value is returned.
+    /// A Fillup value can not update the records already handed to the sink, so
+    /// the pipeline is applied to them as they are handed over. An Error state
+    /// error carries no records, they all went to the sink.
+    pub fn parse_string_into<B, F>(
+        &mut self,
+        input: &str,
+        conversion: Option<DataRecordConversion>,
+        mut sink: F,
+    ) -> Result<ControlFlow<B>, TextFSMError>
+    where
+        F: FnMut(DataRecord) -> ControlFlow<B>,
+    {
+        self.run_lines(input, None, |fsm, _| {
+            for rec in fsm.records.drain(..) {
+                let Some(rec) = fsm.pipeline.apply(rec) else {
+                    trace!("RECORD: dropped by the pipeline");
+                    continue;
+                };
+                match conversion {
+                    None => sink(rec)?,
+                    Some(ref conversion) => sink(conversion.convert_record(&rec))?,
+                }
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Parse the input into a table, with the Key values as the superkey.
+    pub fn parse_table(&mut self, input: &str) -> TextTable {
+        let records = self.parse_string(input, None);
+        let mut table = TextTable::from_records(self.header(), &records);
+        table
+            .set_superkey(self.key_names())
+            .expect("Key values are in the header");
+        table
+    }
 }
@@ -0,0 +1,146 @@
+//! Bounds on the resources of a parse, for input that can not be trusted.
+//!
+//! ```
+//! use std::time::Duration;
+//! use textfsm_rs::limits::{CancelToken, ParseLimits};
+//!
+//! let limits = ParseLimits {
+//!     max_line_length: Some(4096),
+//!     max_records: Some(10_000),
+//!     timeout: Some(Duration::from_secs(5)),
+//!     ..Default::default()
+//! };
+//! let token = CancelToken::new();
+//! // hand a clone to another thread, which can stop the parse with token.cancel()
+//! ```
+
+use crate::{MultiRegex, TextFSM, TextFSMError, TextFSMParser};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Limits of a parse session, all off by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseLimits {
+    /// Bytes in an input line
+    pub max_line_length: Option<usize>,
+    /// Records emitted by the parse
+    pub max_records: Option<usize>,
+    /// Bytes of the whole input
+    pub max_input_size: Option<usize>,
+    /// Backtracking steps of the fancy regexes on a line, instead of the
+    /// `fancy_regex` default of one million
+    pub backtrack_limit: Option<usize>,
+    /// Wall-clock time from the start of the parse
+    pub timeout: Option<Duration>,
+}
+
+/// Stops a parse from another thread. The parse checks it between the lines.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl TextFSMParser {
+    /// Recompile the fancy regexes of the rules with a backtrack limit.
+    pub fn set_backtrack_limit(&mut self, limit: usize) -> Result<(), String> {
+        for state in self.states.values_mut() {
+            for rule in state.rules.iter_mut() {
+                if let Some(MultiRegex::Fancy(ref mut rx)) = rule.maybe_regex {
+                    *rx = fancy_regex::RegexBuilder::new(rx.as_str())
+                        .backtrack_limit(limit)
+                        .build()
+                        .map_err(|e| format!("Error: {} in regex '{}'", e, rx.as_str()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TextFSM {
+    /// Checks done before parsing an input line.
+    pub(crate) fn check_line_limits(
+        &self,
+        started: Instant,
+        line_number: usize,
+        line: &str,
+    ) -> Result<(), TextFSMError> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(TextFSMError::Cancelled {
+                line_number,
+                records: self.records.clone(),
+            });
+        }
+        if let Some(timeout) = self.limits.timeout {
+            if started.elapsed() >= timeout {
+                return Err(TextFSMError::Timeout {
+                    timeout,
+                    line_number,
+                    records: self.records.clone(),
+                });
+            }
+        }
+        if let Some(limit) = self.limits.max_line_length {
+            if line.len() > limit {
+                return Err(TextFSMError::LineTooLong {
+                    length: line.len(),
+                    limit,
+                    line_number,
+                    records: self.records.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks done once a line is parsed, `emitted` counting the records of the
+    /// parse so far.
+    pub(crate) fn check_parsed_line(
+        &mut self,
+        emitted: usize,
+        line_number: usize,
+        line: &str,
+    ) -> Result<(), TextFSMError> {
+        if self.backtrack_limit_exceeded {
+            self.backtrack_limit_exceeded = false;
+            return Err(TextFSMError::BacktrackLimitExceeded {
+                line_number,
+                line: line.to_string(),
+                state: self.curr_state.clone(),
+                records: self.records.clone(),
+            });
+        }
+        if let Some(limit) = self.limits.max_records {
+            if emitted > limit {
+                // only the records within the limit
+                let mut records = self.records.clone();
+                records.truncate(records.len().saturating_sub(emitted - limit));
+                return Err(TextFSMError::TooManyRecords {
+                    limit,
+                    line_number,
+                    records,
+                });
+            }
+        }
+        Ok(())
+    }
+}
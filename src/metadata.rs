@@ -0,0 +1,139 @@
+//! Columns given by the caller, like the device the output was collected from,
+//! added to every record and to the header.
+//!
+//! ```
+//! use textfsm_rs::metadata::{Metadata, MetadataConflict};
+//!
+//! let metadata = Metadata::new()
+//!     .hostname("router1")
+//!     .command("show version")
+//!     .with("SITE", "paris")
+//!     .on_conflict(MetadataConflict::Prefix("META_".to_string()));
+//! ```
+
+use crate::{ParsedOutput, Value};
+
+pub const HOSTNAME: &str = "HOSTNAME";
+pub const PLATFORM: &str = "PLATFORM";
+pub const COMMAND: &str = "COMMAND";
+pub const COLLECTED_AT: &str = "COLLECTED_AT";
+
+/// What to do with a metadata column named like a Value of the template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum MetadataConflict {
+    /// Fail
+    #[default]
+    Error,
+    /// Keep the value parsed by the template
+    KeepTemplate,
+    /// Replace the value parsed by the template
+    Overwrite,
+    /// Add the metadata column with the prefix in front of its name
+    Prefix(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// In the order of the header
+    columns: Vec<(String, String)>,
+    conflict: MetadataConflict,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a column, or replace the value of the column of the same name.
+    pub fn with(mut self, name: &str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        match self.columns.iter_mut().find(|(n, _)| n == name) {
+            Some(column) => column.1 = value,
+            None => self.columns.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    pub fn hostname(self, value: impl Into<String>) -> Self {
+        self.with(HOSTNAME, value)
+    }
+
+    pub fn platform(self, value: impl Into<String>) -> Self {
+        self.with(PLATFORM, value)
+    }
+
+    pub fn command(self, value: impl Into<String>) -> Self {
+        self.with(COMMAND, value)
+    }
+
+    /// Time of the collection of the output, formatted by the caller.
+    pub fn collected_at(self, value: impl Into<String>) -> Self {
+        self.with(COLLECTED_AT, value)
+    }
+
+    pub fn on_conflict(mut self, conflict: MetadataConflict) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Columns to set in the records parsed with these Values, once the
+    /// conflicts are resolved.
+    pub fn resolve(&self, value_names: &[String]) -> Result<Vec<(String, String)>, String> {
+        let mut columns: Vec<(String, String)> = vec![];
+        for (name, value) in &self.columns {
+            if !value_names.contains(name) {
+                columns.push((name.clone(), value.clone()));
+                continue;
+            }
+            match self.conflict {
+                MetadataConflict::Error => {
+                    return Err(format!(
+                        "metadata column '{}' is a Value of the template",
+                        name
+                    ))
+                }
+                MetadataConflict::KeepTemplate => {}
+                MetadataConflict::Overwrite => columns.push((name.clone(), value.clone())),
+                MetadataConflict::Prefix(ref prefix) => {
+                    let prefixed = format!("{}{}", prefix, name);
+                    if value_names.contains(&prefixed) {
+                        return Err(format!(
+                            "metadata column '{}' is a Value of the template",
+                            prefixed
+                        ));
+                    }
+                    columns.push((prefixed, value.clone()));
+                }
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Add the columns to the records and in front of the header of a parse.
+    pub fn apply(&self, out: &mut ParsedOutput) -> Result<(), String> {
+        let columns = self.resolve(&out.header)?;
+        out.header = header_with(&columns, &out.header);
+        for rec in out.records.iter_mut() {
+            for (name, value) in &columns {
+                rec.fields
+                    .insert(name.clone(), Value::Single(value.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The metadata columns that are not Values, then the Values.
+pub(crate) fn header_with(columns: &[(String, String)], value_names: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|(name, _)| name)
+        .filter(|name| !value_names.contains(name))
+        .chain(value_names)
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,169 @@
+use crate::{DataRecord, ParsedOutput, TextTable, Value};
+use std::io::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// Separator of the items of the List values
+    pub list_separator: String,
+    pub write_header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            list_separator: ",".to_string(),
+            write_header: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum JsonShape {
+    /// An object per record, with the values in the order of the header
+    #[default]
+    Objects,
+    /// An array per record, the first one is the header
+    Arrays,
+}
+
+fn cell_text(value: Option<&Value>, list_separator: &str) -> String {
+    match value {
+        Some(Value::Single(s)) => s.clone(),
+        Some(Value::List(l)) => l.join(list_separator),
+        None => String::new(),
+    }
+}
+
+fn value_to_json(value: Option<&Value>) -> serde_json::Value {
+    match value {
+        Some(Value::Single(s)) => serde_json::Value::String(s.clone()),
+        Some(Value::List(l)) => l.iter().cloned().map(serde_json::Value::String).collect(),
+        None => serde_json::Value::Null,
+    }
+}
+
+fn value_to_yaml(value: Option<&Value>) -> serde_yaml::Value {
+    match value {
+        Some(Value::Single(s)) => serde_yaml::Value::String(s.clone()),
+        Some(Value::List(l)) => l.iter().cloned().map(serde_yaml::Value::String).collect(),
+        None => serde_yaml::Value::Null,
+    }
+}
+
+pub fn write_csv<W: Write>(
+    w: W,
+    header: &[String],
+    records: &[DataRecord],
+    options: &CsvOptions,
+) -> std::io::Result<()> {
+    let mut wtr = csv::Writer::from_writer(w);
+    if options.write_header {
+        wtr.write_record(header)?;
+    }
+    for rec in records {
+        let row = header
+            .iter()
+            .map(|name| cell_text(rec.get(name), &options.list_separator));
+        wtr.write_record(row)?;
+    }
+    wtr.flush()
+}
+
+pub fn to_json(header: &[String], records: &[DataRecord], shape: JsonShape) -> serde_json::Value {
+    match shape {
+        JsonShape::Objects => records
+            .iter()
+            .map(|rec| {
+                let fields: serde_json::Map<String, serde_json::Value> = header
+                    .iter()
+                    .map(|name| (name.clone(), value_to_json(rec.get(name))))
+                    .collect();
+                serde_json::Value::Object(fields)
+            })
+            .collect(),
+        JsonShape::Arrays => {
+            let header_row: serde_json::Value = header
+                .iter()
+                .cloned()
+                .map(serde_json::Value::String)
+                .collect();
+            let rows = records.iter().map(|rec| {
+                header
+                    .iter()
+                    .map(|name| value_to_json(rec.get(name)))
+                    .collect::<serde_json::Value>()
+            });
+            std::iter::once(header_row).chain(rows).collect()
+        }
+    }
+}
+
+pub fn write_json<W: Write>(
+    mut w: W,
+    header: &[String],
+    records: &[DataRecord],
+    shape: JsonShape,
+) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut w, &to_json(header, records, shape))?;
+    writeln!(w)
+}
+
+/// The records in the shape of the ntc-templates test files: a `parsed_sample` list
+/// of mappings with lowercase keys.
+pub fn to_parsed_sample_yaml(header: &[String], records: &[DataRecord]) -> serde_yaml::Value {
+    let records: Vec<serde_yaml::Value> = records
+        .iter()
+        .map(|rec| {
+            let fields: serde_yaml::Mapping = header
+                .iter()
+                .map(|name| {
+                    let key = serde_yaml::Value::String(name.to_lowercase());
+                    (key, value_to_yaml(rec.get(name)))
+                })
+                .collect();
+            serde_yaml::Value::Mapping(fields)
+        })
+        .collect();
+    let mut sample = serde_yaml::Mapping::new();
+    sample.insert(
+        serde_yaml::Value::String("parsed_sample".to_string()),
+        serde_yaml::Value::Sequence(records),
+    );
+    serde_yaml::Value::Mapping(sample)
+}
+
+pub fn write_parsed_sample_yaml<W: Write>(
+    mut w: W,
+    header: &[String],
+    records: &[DataRecord],
+) -> std::io::Result<()> {
+    let yaml = serde_yaml::to_string(&to_parsed_sample_yaml(header, records))
+        .map_err(std::io::Error::other)?;
+    write!(w, "---\n{}", yaml)
+}
+
+/// Human-readable table, see `TextTable::formatted_table`.
+pub fn to_text_table(header: &[String], records: &[DataRecord], width: usize) -> String {
+    TextTable::from_records(header.to_vec(), records).formatted_table(width)
+}
+
+impl ParsedOutput {
+    pub fn to_csv(&self, options: &CsvOptions) -> String {
+        let mut out: Vec<u8> = vec![];
+        write_csv(&mut out, &self.header, &self.records, options)
+            .expect("writing to memory can not fail");
+        String::from_utf8(out).expect("csv of strings is utf-8")
+    }
+
+    pub fn to_json(&self, shape: JsonShape) -> serde_json::Value {
+        to_json(&self.header, &self.records, shape)
+    }
+
+    pub fn to_parsed_sample_yaml(&self) -> serde_yaml::Value {
+        to_parsed_sample_yaml(&self.header, &self.records)
+    }
+
+    pub fn to_text_table(&self, width: usize) -> String {
+        to_text_table(&self.header, &self.records, width)
+    }
+}
@@ -0,0 +1,219 @@
+//! Post-processing of the records of a parse.
+//!
+//! ```
+//! use textfsm_rs::pipeline::{transforms, Pipeline};
+//!
+//! let pipeline = Pipeline::new()
+//!     .with_transform(transforms::trim())
+//!     .with_transform(transforms::empty_if(&["N/A", "--"]))
+//!     .with_value_transform("INTERFACE", transforms::normalize_interface_name());
+//! ```
+
+use crate::{DataRecord, Value};
+use std::fmt;
+use std::sync::Arc;
+
+pub type ValueTransform = Arc<dyn Fn(Value) -> Value + Send + Sync>;
+/// Returns `None` to drop the record
+pub type RecordTransform = Arc<dyn Fn(DataRecord) -> Option<DataRecord> + Send + Sync>;
+
+#[derive(Clone)]
+enum Step {
+    /// Applied to the listed values, or to all of them
+    Value(Option<Vec<String>>, ValueTransform),
+    Record(RecordTransform),
+}
+
+/// Transforms applied in order to every record of a parse session.
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Value(Some(names), _) => format!("Value({})", names.join(",")),
+                Step::Value(None, _) => "Value(*)".to_string(),
+                Step::Record(_) => "Record".to_string(),
+            })
+            .collect();
+        f.debug_struct("Pipeline").field("steps", &steps).finish()
+    }
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Transform every value.
+    pub fn with_transform<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        self.steps.push(Step::Value(None, Arc::new(f)));
+        self
+    }
+
+    /// Transform one value.
+    pub fn with_value_transform<F>(self, name: &str, f: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        self.with_values_transform(&[name], f)
+    }
+
+    /// Transform the listed values.
+    pub fn with_values_transform<F>(mut self, names: &[&str], f: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        let names = names.iter().map(|s| s.to_string()).collect();
+        self.steps.push(Step::Value(Some(names), Arc::new(f)));
+        self
+    }
+
+    /// Transform the whole record, or drop it by returning `None`.
+    pub fn with_record_transform<F>(mut self, f: F) -> Self
+    where
+        F: Fn(DataRecord) -> Option<DataRecord> + Send + Sync + 'static,
+    {
+        self.steps.push(Step::Record(Arc::new(f)));
+        self
+    }
+
+    /// Append the steps of another pipeline.
+    pub fn then(mut self, other: Pipeline) -> Self {
+        self.steps.extend(other.steps);
+        self
+    }
+
+    pub fn apply(&self, mut rec: DataRecord) -> Option<DataRecord> {
+        for step in &self.steps {
+            match step {
+                Step::Value(None, f) => {
+                    for value in rec.fields.values_mut() {
+                        let old = std::mem::replace(value, Value::Single(String::new()));
+                        *value = f(old);
+                    }
+                }
+                Step::Value(Some(names), f) => {
+                    for name in names {
+                        if let Some(value) = rec.fields.get_mut(name) {
+                            let old = std::mem::replace(value, Value::Single(String::new()));
+                            *value = f(old);
+                        }
+                    }
+                }
+                Step::Record(f) => rec = f(rec)?,
+            }
+        }
+        Some(rec)
+    }
+}
+
+/// Built-in transforms for common network data.
+pub mod transforms {
+    use crate::{DataRecord, Value};
+
+    fn map_strings(value: Value, f: impl Fn(String) -> String) -> Value {
+        match value {
+            Value::Single(s) => Value::Single(f(s)),
+            Value::List(l) => Value::List(l.into_iter().map(f).collect()),
+        }
+    }
+
+    pub fn trim() -> impl Fn(Value) -> Value + Send + Sync {
+        |value| map_strings(value, |s| s.trim().to_string())
+    }
+
+    /// Replace the placeholders for a missing value, like `N/A` or `--`, with "".
+    pub fn empty_if(markers: &[&str]) -> impl Fn(Value) -> Value + Send + Sync {
+        let markers: Vec<String> = markers.iter().map(|s| s.to_string()).collect();
+        move |value| {
+            map_strings(value, |s| {
+                if markers.contains(&s) {
+                    String::new()
+                } else {
+                    s
+                }
+            })
+        }
+    }
+
+    /// Split a single value into a list, dropping the empty items.
+    pub fn split_list(separator: &str) -> impl Fn(Value) -> Value + Send + Sync {
+        let separator = separator.to_string();
+        move |value| match value {
+            Value::Single(s) => Value::List(
+                s.split(separator.as_str())
+                    .map(|item| item.trim())
+                    .filter(|item| !item.is_empty())
+                    .map(|item| item.to_string())
+                    .collect(),
+            ),
+            list => list,
+        }
+    }
+
+    /// Interface types, the order decides between the names sharing a prefix.
+    /// Names like `mgmt0` are left alone, they mean a different interface on each
+    /// platform.
+    const INTERFACE_TYPES: &[&str] = &[
+        "GigabitEthernet",
+        "FastEthernet",
+        "TenGigabitEthernet",
+        "TwoGigabitEthernet",
+        "TwentyFiveGigE",
+        "FortyGigabitEthernet",
+        "HundredGigE",
+        "Ethernet",
+        "Loopback",
+        "Port-channel",
+        "Vlan",
+        "Tunnel",
+        "Serial",
+        "Management",
+    ];
+
+    /// Expand the abbreviated interface names: `Gi0/1` becomes `GigabitEthernet0/1`.
+    pub fn normalize_interface_name() -> impl Fn(Value) -> Value + Send + Sync {
+        |value| map_strings(value, expand_interface_name)
+    }
+
+    pub fn expand_interface_name(name: String) -> String {
+        let split = name
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(name.len());
+        let (kind, number) = name.split_at(split);
+        let kind = kind.trim().to_lowercase();
+        if kind.len() < 2 || number.is_empty() {
+            return name;
+        }
+        for full in INTERFACE_TYPES {
+            if full.to_lowercase().starts_with(&kind) {
+                return format!("{}{}", full, number);
+            }
+        }
+        name
+    }
+
+    /// Drop the records where all the values are empty.
+    pub fn drop_empty() -> impl Fn(DataRecord) -> Option<DataRecord> + Send + Sync {
+        |rec| {
+            let has_values = rec.iter().any(|(_, value)| match value {
+                Value::Single(s) => !s.is_empty(),
+                Value::List(l) => !l.is_empty(),
+            });
+            has_values.then_some(rec)
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Clean-up of the captured input before it is parsed.
+//!
+//! ```
+//! use textfsm_rs::preprocess::{filters, Preprocessor};
+//!
+//! let preprocessor = Preprocessor::new()
+//!     .with_filter(filters::strip_ansi())
+//!     .with_filter(|input: &str| input.replace("\t", "    "));
+//! assert_eq!(preprocessor.apply("\x1b[1mup\x1b[0m\tdown"), "up    down");
+//! ```
+//!
+//! The built-in filters keep the lines where they are, so that the line numbers
+//! of the errors and of the provenance are the ones of the input.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+/// Transformation of the whole input text.
+pub trait InputFilter: Send + Sync {
+    fn filter(&self, input: &str) -> String;
+}
+
+impl<F> InputFilter for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn filter(&self, input: &str) -> String {
+        self(input)
+    }
+}
+
+/// Filters applied in order to the input of a parse session.
+#[derive(Clone, Default)]
+pub struct Preprocessor {
+    filters: Vec<Arc<dyn InputFilter>>,
+}
+
+impl fmt::Debug for Preprocessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Preprocessor")
+            .field("filters", &self.filters.len())
+            .finish()
+    }
+}
+
+impl Preprocessor {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// All the built-in filters, for the transcripts of interactive sessions.
+    pub fn cli_session() -> Self {
+        Preprocessor::new()
+            .with_filter(filters::normalize_newlines())
+            .with_filter(filters::resolve_overwrites())
+            .with_filter(filters::strip_ansi())
+            .with_filter(filters::remove_pager_prompts())
+            .with_filter(filters::trim_trailing_whitespace())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn with_filter<F: InputFilter + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Append the filters of another preprocessor.
+    pub fn then(mut self, other: Preprocessor) -> Self {
+        self.filters.extend(other.filters);
+        self
+    }
+
+    pub fn apply<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(input);
+        for filter in &self.filters {
+            text = Cow::Owned(filter.filter(&text));
+        }
+        text
+    }
+}
+
+/// Built-in filters for captured CLI sessions.
+pub mod filters {
+    use regex::Regex;
+
+    /// Pager prompts of the common platforms: `--More--`, `-- More --`,
+    /// `---- More ----`, `---(more 45%)---`, `<--- More --->`.
+    pub const PAGER_PROMPTS: &[&str] = &[
+        r"(?i)<?-{2,} ?\(?more( \d+%)?\)? ?-{2,}>?",
+        r"(?i)press any key to continue( \(q to quit\))?",
+    ];
+
+    fn map_lines(input: &str, f: impl Fn(&str) -> String) -> String {
+        input.split('\n').map(f).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Turn the CRLF line endings into LF.
+    pub fn normalize_newlines() -> impl Fn(&str) -> String + Send + Sync {
+        |input: &str| input.replace("\r\n", "\n")
+    }
+
+    /// Remove the ANSI escape sequences: colours, cursor moves, window titles.
+    pub fn strip_ansi() -> impl Fn(&str) -> String + Send + Sync {
+        let re =
+            Regex::new(r"\x1b(\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(\x07|\x1b\\)|[@-Z\\-_])").unwrap();
+        move |input: &str| re.replace_all(input, "").into_owned()
+    }
+
+    /// Replay the backspaces, the carriage returns and the cursor moves to the
+    /// left (`ESC [ n D`) like a terminal would, keeping what is left on screen.
+    /// The other escape sequences take no room on screen and are dropped.
+    pub fn resolve_overwrites() -> impl Fn(&str) -> String + Send + Sync {
+        |input: &str| map_lines(input, resolve_line)
+    }
+
+    fn resolve_line(line: &str) -> String {
+        if !line.contains(['\x08', '\r', '\x1b']) {
+            return line.to_string();
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let mut screen: Vec<char> = vec![];
+        let mut pos: usize = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            i += 1;
+            match c {
+                '\x08' => pos = pos.saturating_sub(1),
+                // a line ending with CR keeps its text
+                '\r' if i < chars.len() => pos = 0,
+                '\r' => {}
+                '\x1b' if chars.get(i) == Some(&'[') => {
+                    let params = chars[i + 1..]
+                        .iter()
+                        .take_while(|c| ('0'..='?').contains(c))
+                        .count();
+                    let Some(&cmd) = chars.get(i + 1 + params) else {
+                        break;
+                    };
+                    if cmd == 'D' {
+                        let n: String = chars[i + 1..i + 1 + params].iter().collect();
+                        pos = pos.saturating_sub(n.parse().unwrap_or(1));
+                    }
+                    i += params + 2;
+                }
+                c => {
+                    if pos < screen.len() {
+                        screen[pos] = c;
+                    } else {
+                        screen.push(c);
+                    }
+                    pos += 1;
+                }
+            }
+        }
+        screen.into_iter().collect()
+    }
+
+    /// Remove the pager prompts, with the space around them.
+    pub fn remove_pager_prompts() -> impl Fn(&str) -> String + Send + Sync {
+        remove_prompts(PAGER_PROMPTS).expect("valid pager prompts")
+    }
+
+    /// Remove the text matching the patterns, with the space around it.
+    pub fn remove_prompts(
+        patterns: &[&str],
+    ) -> Result<impl Fn(&str) -> String + Send + Sync, regex::Error> {
+        let res = patterns
+            .iter()
+            .map(|pattern| Regex::new(&format!(" ?(?:{}) ?", pattern)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(move |input: &str| {
+            res.iter().fold(input.to_string(), |text, re| {
+                re.replace_all(&text, "").into_owned()
+            })
+        })
+    }
+
+    pub fn trim_trailing_whitespace() -> impl Fn(&str) -> String + Send + Sync {
+        |input: &str| map_lines(input, |line| line.trim_end().to_string())
+    }
+}
@@ -0,0 +1,89 @@
+//! Where the records and their values come from in the input.
+//!
+//! Tracking is off by default, see `TextFSM::with_provenance`. The provenance is
+//! not serialized with the record, unless asked with
+//! `DataRecord::serialize_with_provenance`.
+
+use crate::DataRecord;
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Capture of a value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueProvenance {
+    /// Input line, numbered from 1
+    pub line: usize,
+    /// Byte span of the captured text in the line
+    pub span: Range<usize>,
+    pub state: String,
+    /// Index of the rule in the state, from 0
+    pub rule_index: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RecordProvenance {
+    /// Input lines the record was built from
+    pub first_line: usize,
+    pub last_line: usize,
+    /// Captures of the values, one per item of a List value
+    pub values: HashMap<String, Vec<ValueProvenance>>,
+}
+
+impl RecordProvenance {
+    pub fn value(&self, name: &str) -> Option<&[ValueProvenance]> {
+        self.values.get(name).map(|v| v.as_slice())
+    }
+}
+
+impl DataRecord {
+    pub fn provenance(&self) -> Option<&RecordProvenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Captures of a value of the record.
+    pub fn value_provenance(&self, name: &str) -> Option<&[ValueProvenance]> {
+        self.provenance.as_ref()?.value(name)
+    }
+
+    pub(crate) fn set_value_provenance(&mut self, name: &str, capture: ValueProvenance) {
+        self.provenance
+            .get_or_insert_with(Default::default)
+            .values
+            .insert(name.to_string(), vec![capture]);
+    }
+
+    /// Follow `append_value`: the captures of a List value accumulate, the ones of a
+    /// single value are replaced.
+    pub(crate) fn append_value_provenance(&mut self, name: &str, captures: &[ValueProvenance]) {
+        let is_list = matches!(self.fields.get(name), Some(crate::Value::List(_)));
+        let values = &mut self.provenance.get_or_insert_with(Default::default).values;
+        if is_list {
+            values
+                .entry(name.to_string())
+                .or_default()
+                .extend_from_slice(captures);
+        } else {
+            values.insert(name.to_string(), captures.to_vec());
+        }
+    }
+
+    /// Serialize the fields of the record, with its provenance under `_provenance`.
+    pub fn serialize_with_provenance(&self) -> WithProvenance<'_> {
+        WithProvenance(self)
+    }
+}
+
+pub struct WithProvenance<'a>(&'a DataRecord);
+
+impl Serialize for WithProvenance<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.fields.len() + 1))?;
+        for (name, value) in self.0.iter() {
+            map.serialize_entry(name, value)?;
+        }
+        map.serialize_entry("_provenance", &self.0.provenance)?;
+        map.end()
+    }
+}
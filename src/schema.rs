@@ -0,0 +1,90 @@
+use crate::typed::ValueType;
+use crate::{TextFSMParser, ValueDefinition};
+use serde_json::{json, Map, Value as JsonValue};
+
+pub const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaOptions {
+    pub title: Option<String>,
+    /// Describe the values converted to the types of the `# @type` directives,
+    /// as returned by `TextFSM::parse_string_typed`
+    pub typed: bool,
+}
+
+fn item_schema(typ: Option<&ValueType>) -> JsonValue {
+    match typ {
+        None | Some(ValueType::Str) => json!({"type": "string"}),
+        Some(ValueType::Int) => json!({"type": "integer"}),
+        Some(ValueType::Float) => json!({"type": "number"}),
+        Some(ValueType::Bool) => json!({"type": "boolean"}),
+        Some(ValueType::Ipv4) => json!({"type": "string", "format": "ipv4"}),
+        Some(ValueType::Ipv6) => json!({"type": "string", "format": "ipv6"}),
+        Some(ValueType::Mac) => json!({
+            "type": "string",
+            "pattern": "^([0-9a-f]{2}:){5}[0-9a-f]{2}$"
+        }),
+        // the converter decides what it returns
+        Some(ValueType::Custom(name)) => json!({"x-textfsm-type": name}),
+    }
+}
+
+fn value_schema(val: &ValueDefinition, typ: Option<&ValueType>) -> JsonValue {
+    let mut item = item_schema(typ);
+    // a typed value that captured nothing is null, unless it is Required
+    if typ.is_some() && !val.is_required {
+        item = json!({"anyOf": [item, {"type": "null"}]});
+    }
+    let mut schema = if val.is_list {
+        let mut list = json!({"type": "array", "items": item});
+        if val.is_required {
+            list["minItems"] = json!(1);
+        }
+        list
+    } else {
+        item
+    };
+    if val.is_key {
+        schema["x-textfsm-key"] = json!(true);
+    }
+    schema
+}
+
+impl TextFSMParser {
+    /// JSON Schema of a record produced by the template.
+    ///
+    /// Every value is a required property, as every record has all of them. The
+    /// List values are arrays, and the Key values carry `"x-textfsm-key": true`.
+    /// Other properties are allowed, for the metadata columns.
+    pub fn json_schema(&self, options: &SchemaOptions) -> JsonValue {
+        let mut properties = Map::new();
+        let mut required: Vec<JsonValue> = vec![];
+        let mut keys: Vec<JsonValue> = vec![];
+        for name in &self.value_names {
+            let val = &self.values[name];
+            let typ = if options.typed {
+                self.value_types.get(name)
+            } else {
+                None
+            };
+            properties.insert(name.clone(), value_schema(val, typ));
+            required.push(json!(name));
+            if val.is_key {
+                keys.push(json!(name));
+            }
+        }
+
+        let mut schema = Map::new();
+        schema.insert("$schema".to_string(), json!(JSON_SCHEMA_DRAFT));
+        if let Some(ref title) = options.title {
+            schema.insert("title".to_string(), json!(title));
+        }
+        schema.insert("type".to_string(), json!("object"));
+        schema.insert("properties".to_string(), JsonValue::Object(properties));
+        schema.insert("required".to_string(), JsonValue::Array(required));
+        if !keys.is_empty() {
+            schema.insert("x-textfsm-keys".to_string(), JsonValue::Array(keys));
+        }
+        JsonValue::Object(schema)
+    }
+}
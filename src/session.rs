@@ -0,0 +1,141 @@
+//! Split the transcript of a CLI session into its commands, and parse each of
+//! them with the template of the index.
+//!
+//! Transcripts captured from an interactive session usually need
+//! `preprocess::Preprocessor::cli_session` first, to drop the pager prompts.
+
+use crate::{CliTable, ParsedOutput};
+use regex::Regex;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Prompts like `router#`, `router>`, `router(config-if)#`, `user@router>`,
+/// `<huawei>` and `[huawei]`, followed by the command.
+pub const DEFAULT_PROMPT: &str =
+    r"^[<\[]?(?P<hostname>[\w.\-@/:]{1,63})(?:\([\w.\-]*\))?[>#\]]\s*(?P<command>.*)$";
+
+/// A command of the session, with its output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub hostname: String,
+    pub command: String,
+    /// Lines between this prompt and the next one
+    pub output: String,
+    /// Transcript line of the prompt, numbered from 1
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionSplitter {
+    prompt: Regex,
+}
+
+impl Default for SessionSplitter {
+    fn default() -> Self {
+        SessionSplitter::new(DEFAULT_PROMPT).expect("valid default prompt")
+    }
+}
+
+impl SessionSplitter {
+    /// Use another prompt regex. It must have a `command` group, and may have a
+    /// `hostname` group.
+    pub fn new(prompt: &str) -> Result<Self, String> {
+        let prompt = Regex::new(prompt).map_err(|e| e.to_string())?;
+        if !prompt.capture_names().any(|name| name == Some("command")) {
+            return Err(format!("prompt regex '{}' has no 'command' group", prompt));
+        }
+        Ok(SessionSplitter { prompt })
+    }
+
+    /// Split the transcript at the prompts followed by a command.
+    ///
+    /// Once a prompt is found, only the prompts with the same hostname start a
+    /// new command, so that the output lines looking like a prompt are kept.
+    /// The text before the first command and the prompts with no command are
+    /// dropped.
+    pub fn split(&self, transcript: &str) -> Vec<CommandOutput> {
+        let mut commands: Vec<CommandOutput> = vec![];
+        let mut current: Option<CommandOutput> = None;
+        let mut device: Option<String> = None;
+        for (lineno, line) in transcript.lines().enumerate() {
+            let prompt = self.prompt.captures(line).and_then(|caps| {
+                let hostname = caps.name("hostname").map_or("", |m| m.as_str());
+                match device {
+                    Some(ref device) if device != hostname => None,
+                    _ => Some((hostname.to_string(), caps["command"].trim().to_string())),
+                }
+            });
+            match prompt {
+                Some((hostname, command)) => {
+                    commands.extend(current.take());
+                    if !command.is_empty() {
+                        current = Some(CommandOutput {
+                            hostname: hostname.clone(),
+                            command,
+                            output: String::new(),
+                            line: lineno + 1,
+                        });
+                    }
+                    device = Some(hostname);
+                }
+                None => {
+                    if let Some(ref mut current) = current {
+                        current.output.push_str(line);
+                        current.output.push('\n');
+                    }
+                }
+            }
+        }
+        commands.extend(current);
+        commands
+    }
+}
+
+/// The command of the session parsed with its template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedCommand {
+    pub command: CommandOutput,
+    pub parsed: ParsedOutput,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedSession {
+    /// In the order of the session
+    pub parsed: Vec<ParsedCommand>,
+    /// Commands with no template in the index
+    pub no_template: Vec<CommandOutput>,
+    /// Commands whose template failed, like reaching the Error state
+    pub failed: Vec<(CommandOutput, Box<dyn Error>)>,
+}
+
+impl CliTable {
+    /// Split the transcript with the splitter and parse the output of each
+    /// command with the template of the index for the platform.
+    ///
+    /// The hostname of the prompt is passed as the `Hostname` attribute.
+    pub fn parse_session(
+        &self,
+        transcript: &str,
+        platform: &str,
+        splitter: &SessionSplitter,
+    ) -> ParsedSession {
+        let mut session = ParsedSession::default();
+        for command in splitter.split(transcript) {
+            let mut attributes: HashMap<String, String> = HashMap::new();
+            attributes.insert("Platform".to_string(), platform.to_string());
+            attributes.insert("Command".to_string(), command.command.clone());
+            if !command.hostname.is_empty() {
+                attributes.insert("Hostname".to_string(), command.hostname.clone());
+            }
+            if self.get_template_for_attributes(&attributes).is_none() {
+                session.no_template.push(command);
+                continue;
+            }
+            match self.parse_cmd(&command.output, &attributes) {
+                Ok(parsed) => session.parsed.push(ParsedCommand { command, parsed }),
+                Err(e) => session.failed.push((command, e)),
+            }
+        }
+        session
+    }
+}
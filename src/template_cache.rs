@@ -0,0 +1,91 @@
+use crate::TextFSMParser;
+use log::debug;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+struct CachedTemplate {
+    parser: Arc<TextFSMParser>,
+    mtime: Option<SystemTime>,
+}
+
+/// Thread-safe cache of compiled templates, keyed by the resolved template path.
+///
+/// With mtime checking enabled, a template whose file was modified since it was
+/// compiled is read and compiled again on the next lookup.
+#[derive(Debug, Default)]
+pub struct TemplateCache {
+    entries: RwLock<HashMap<PathBuf, CachedTemplate>>,
+    check_mtime: bool,
+}
+
+impl TemplateCache {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_mtime_check(mut self, check_mtime: bool) -> Self {
+        self.check_mtime = check_mtime;
+        self
+    }
+
+    fn resolve_path(path: &Path) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    fn get_mtime(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Return the compiled template, reading and compiling it on the first use.
+    pub fn get(&self, path: &Path) -> Result<Arc<TextFSMParser>, String> {
+        let resolved = Self::resolve_path(path);
+        let mtime = if self.check_mtime {
+            Self::get_mtime(&resolved)
+        } else {
+            None
+        };
+        if let Some(cached) = self.entries.read().unwrap().get(&resolved) {
+            if !self.check_mtime || cached.mtime == mtime {
+                return Ok(cached.parser.clone());
+            }
+            debug!("template {} changed, recompiling", resolved.display());
+        }
+
+        let template = std::fs::read_to_string(&resolved)
+            .map_err(|e| format!("template {}: {}", resolved.display(), e))?;
+        let parser = TextFSMParser::from_string(&template)
+            .map_err(|e| format!("template {}: {}", resolved.display(), e))?;
+        let parser = Arc::new(parser);
+        let cached = CachedTemplate {
+            parser: parser.clone(),
+            mtime,
+        };
+        self.entries.write().unwrap().insert(resolved, cached);
+        Ok(parser)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        let resolved = Self::resolve_path(path);
+        self.entries.read().unwrap().contains_key(&resolved)
+    }
+
+    pub fn invalidate(&self, path: &Path) {
+        let resolved = Self::resolve_path(path);
+        self.entries.write().unwrap().remove(&resolved);
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
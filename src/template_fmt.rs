@@ -0,0 +1,97 @@
+use crate::TextFSMParser;
+
+fn format_value_line(line: &str) -> String {
+    let mut rest = line.trim()["Value".len()..].trim_start();
+    let mut words: Vec<&str> = vec!["Value"];
+    // options and the name come before the regex, which is kept as it is
+    while !rest.is_empty() && !rest.starts_with('(') {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        words.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() {
+        words.push(rest);
+    }
+    words.join(" ")
+}
+
+fn format_rule_line(line: &str) -> String {
+    let rule = line.trim_start();
+    match rule.find(" -> ") {
+        // the rule match is kept byte for byte, its trailing whitespace is a part of the regex
+        Some(pos) => {
+            let action = rule[pos + 4..].trim();
+            let (words, message) = match action.find('"') {
+                Some(quote) => (&action[..quote], &action[quote..]),
+                None => (action, ""),
+            };
+            let mut action: Vec<&str> = words.split_whitespace().collect();
+            if !message.is_empty() {
+                action.push(message);
+            }
+            format!("  {} -> {}", &rule[..pos], action.join(" "))
+        }
+        None => format!("  {}", rule.trim_end()),
+    }
+}
+
+/// Reformat a template in the canonical layout: single spaces between the tokens
+/// of the Value lines, rules indented by two spaces with a single ` -> ` before the
+/// action, and one blank line before every state.
+///
+/// The template must compile, and the result is checked to define the same values
+/// and states.
+pub fn format_template(template: &str) -> Result<String, String> {
+    let parser = TextFSMParser::from_string(template)?;
+    let mut out: Vec<String> = vec![];
+    let mut in_states = false;
+
+    let needs_separator = |out: &Vec<String>| match out.last() {
+        Some(last) => !last.is_empty() && !last.starts_with('#'),
+        None => false,
+    };
+
+    for line in template.lines() {
+        let trimmed = line.trim();
+        let indented = line.starts_with(char::is_whitespace);
+        if trimmed.is_empty() {
+            // inside the states the separators are added before the state names
+            if !in_states && needs_separator(&out) {
+                out.push(String::new());
+            }
+        } else if trimmed.starts_with('#') {
+            if in_states && indented {
+                out.push(format!("  {}", trimmed));
+            } else {
+                if in_states && needs_separator(&out) {
+                    out.push(String::new());
+                }
+                out.push(trimmed.to_string());
+            }
+        } else if !in_states && !indented && trimmed.split_whitespace().next() == Some("Value") {
+            out.push(format_value_line(line));
+        } else if indented {
+            out.push(format_rule_line(line));
+        } else {
+            in_states = true;
+            if needs_separator(&out) {
+                out.push(String::new());
+            }
+            out.push(trimmed.to_string());
+        }
+    }
+    while matches!(out.last(), Some(last) if last.is_empty()) {
+        out.pop();
+    }
+    let mut formatted = out.join("\n");
+    formatted.push('\n');
+
+    let reformatted = TextFSMParser::from_string(&formatted)
+        .map_err(|e| format!("formatted template does not compile: {}", e))?;
+    if reformatted.value_names != parser.value_names
+        || reformatted.state_names != parser.state_names
+    {
+        return Err("formatted template differs from the original".to_string());
+    }
+    Ok(formatted)
+}
@@ -0,0 +1,428 @@
+use crate::{DataRecord, ParsedOutput, Value};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Table of values with a header, the counterpart of Python TextFSM `texttable.TextTable`.
+///
+/// The superkey lists the columns that identify a row, usually the Key values of
+/// the template; it is used by the default sort order and as the labels of the
+/// label/value output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextTable {
+    header: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    superkey: Vec<String>,
+}
+
+/// A row of a `TextTable`, giving access to its cells by column name.
+#[derive(Debug, Clone, Copy)]
+pub struct TableRow<'a> {
+    header: &'a [String],
+    values: &'a [Value],
+}
+
+impl<'a> TableRow<'a> {
+    pub fn get(&self, column: &str) -> Option<&'a Value> {
+        let index = self.header.iter().position(|name| name == column)?;
+        self.values.get(index)
+    }
+
+    pub fn values(&self) -> &'a [Value] {
+        self.values
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a String, &'a Value)> {
+        self.header.iter().zip(self.values.iter())
+    }
+
+    /// True if any of the cells is not empty.
+    pub fn has_values(&self) -> bool {
+        self.values.iter().any(|value| !value_is_empty(value))
+    }
+}
+
+fn value_is_empty(value: &Value) -> bool {
+    match value {
+        Value::Single(s) => s.is_empty(),
+        Value::List(l) => l.is_empty(),
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Single(s) => s.clone(),
+        Value::List(l) => l.join(", "),
+    }
+}
+
+/// Total order of the cells: the numbers first, in numeric order, then the other
+/// text in lexical order.
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// The single values before the lists, which compare element by element.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Single(a), Value::Single(b)) => compare_text(a, b),
+        (Value::List(a), Value::List(b)) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| compare_text(a, b))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Single(_), Value::List(_)) => Ordering::Less,
+        (Value::List(_), Value::Single(_)) => Ordering::Greater,
+    }
+}
+
+/// Greedy word wrap; a word longer than the width gets a line of its own.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+impl TextTable {
+    pub fn new(header: Vec<String>) -> Self {
+        TextTable {
+            header,
+            ..Default::default()
+        }
+    }
+
+    /// Build a table from records, the missing fields become empty values.
+    pub fn from_records(header: Vec<String>, records: &[DataRecord]) -> Self {
+        let mut table = TextTable::new(header);
+        for rec in records {
+            table.append_record(rec);
+        }
+        table
+    }
+
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn row(&self, index: usize) -> Option<TableRow<'_>> {
+        self.rows.get(index).map(|values| TableRow {
+            header: &self.header,
+            values,
+        })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = TableRow<'_>> {
+        self.rows.iter().map(move |values| TableRow {
+            header: &self.header,
+            values,
+        })
+    }
+
+    pub fn get(&self, index: usize, column: &str) -> Option<&Value> {
+        self.row(index)?.get(column)
+    }
+
+    fn column_index(&self, column: &str) -> Result<usize, String> {
+        self.header
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| format!("unknown column '{}'", column))
+    }
+
+    pub fn append(&mut self, row: Vec<Value>) -> Result<(), String> {
+        if row.len() != self.header.len() {
+            return Err(format!(
+                "row has {} values, the header has {} columns",
+                row.len(),
+                self.header.len()
+            ));
+        }
+        self.rows.push(row);
+        Ok(())
+    }
+
+    pub fn append_record(&mut self, rec: &DataRecord) {
+        let row = self
+            .header
+            .iter()
+            .map(|name| {
+                rec.get(name)
+                    .cloned()
+                    .unwrap_or_else(|| Value::Single(String::new()))
+            })
+            .collect();
+        self.rows.push(row);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Vec<Value> {
+        self.rows.remove(index)
+    }
+
+    /// Add a column at the end, with the default value in the existing rows.
+    pub fn add_column(&mut self, column: &str, default: Value) -> Result<(), String> {
+        if self.header.iter().any(|name| name == column) {
+            return Err(format!("column '{}' already exists", column));
+        }
+        self.header.push(column.to_string());
+        for row in self.rows.iter_mut() {
+            row.push(default.clone());
+        }
+        Ok(())
+    }
+
+    pub fn superkey(&self) -> &[String] {
+        &self.superkey
+    }
+
+    pub fn set_superkey(&mut self, columns: Vec<String>) -> Result<(), String> {
+        for column in &columns {
+            self.column_index(column)?;
+        }
+        self.superkey = columns;
+        Ok(())
+    }
+
+    /// Values of the superkey columns of a row.
+    pub fn key_value(&self, index: usize) -> Option<Vec<&Value>> {
+        let row = self.row(index)?;
+        Some(
+            self.superkey
+                .iter()
+                .filter_map(|column| row.get(column))
+                .collect(),
+        )
+    }
+
+    /// Sort the rows by the superkey, or by all the columns if there is none.
+    pub fn sort(&mut self) {
+        let columns: Vec<String> = if self.superkey.is_empty() {
+            self.header.clone()
+        } else {
+            self.superkey.clone()
+        };
+        let columns: Vec<&str> = columns.iter().map(|s| s.as_str()).collect();
+        self.sort_by_columns(&columns, false)
+            .expect("superkey columns are in the header");
+    }
+
+    pub fn sort_by_columns(&mut self, columns: &[&str], reverse: bool) -> Result<(), String> {
+        let indexes = columns
+            .iter()
+            .map(|column| self.column_index(column))
+            .collect::<Result<Vec<usize>, String>>()?;
+        self.rows.sort_by(|a, b| {
+            let ord = indexes
+                .iter()
+                .map(|i| compare_values(&a[*i], &b[*i]))
+                .find(|ord| *ord != Ordering::Equal)
+                .unwrap_or(Ordering::Equal);
+            if reverse {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        Ok(())
+    }
+
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&TableRow<'_>, &TableRow<'_>) -> Ordering,
+    {
+        let header = &self.header;
+        self.rows.sort_by(|a, b| {
+            let a = TableRow { header, values: a };
+            let b = TableRow { header, values: b };
+            compare(&a, &b)
+        });
+    }
+
+    /// A new table with the rows the predicate accepts.
+    pub fn filter<F>(&self, mut predicate: F) -> TextTable
+    where
+        F: FnMut(&TableRow<'_>) -> bool,
+    {
+        let rows = self
+            .rows()
+            .filter(|row| predicate(row))
+            .map(|row| row.values.to_vec())
+            .collect();
+        TextTable {
+            header: self.header.clone(),
+            rows,
+            superkey: self.superkey.clone(),
+        }
+    }
+
+    /// A new table without the rows where all the cells are empty.
+    pub fn filter_empty(&self) -> TextTable {
+        self.filter(|row| row.has_values())
+    }
+
+    /// Width of the terminal from `$COLUMNS`, 80 if it is not set.
+    pub fn terminal_width() -> usize {
+        std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80)
+    }
+
+    /// The table with aligned columns, the header underlined with `=`.
+    ///
+    /// When the table does not fit in the width, the widest columns are wrapped at
+    /// word boundaries, and the rows spanning several lines are delimited by a line
+    /// of `-`.
+    pub fn formatted_table(&self, width: usize) -> String {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(value_text).collect())
+            .collect();
+        let all_rows = || std::iter::once(&self.header).chain(cells.iter());
+        let mut widths: Vec<usize> = (0..self.header.len())
+            .map(|i| {
+                all_rows()
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        // narrowest a column can get without breaking words
+        let min_widths: Vec<usize> = (0..self.header.len())
+            .map(|i| {
+                all_rows()
+                    .flat_map(|row| row[i].split_whitespace().map(|w| w.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        // every column is padded by a space on both sides
+        while widths.iter().map(|w| w + 2).sum::<usize>() > width {
+            let widest = (0..widths.len())
+                .filter(|i| widths[*i] > min_widths[*i])
+                .max_by_key(|i| widths[*i]);
+            match widest {
+                Some(i) => widths[i] -= 1,
+                None => break,
+            }
+        }
+
+        let format_row = |row: &[String]| -> Vec<String> {
+            let wrapped: Vec<Vec<String>> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| wrap_text(cell, *width))
+                .collect();
+            let height = wrapped.iter().map(|lines| lines.len()).max().unwrap_or(1);
+            (0..height)
+                .map(|line| {
+                    wrapped
+                        .iter()
+                        .zip(&widths)
+                        .map(|(lines, width)| {
+                            let text = lines.get(line).map(|s| s.as_str()).unwrap_or("");
+                            format!(" {:width$} ", text, width = width)
+                        })
+                        .collect::<String>()
+                })
+                .collect()
+        };
+
+        let total: usize = widths.iter().map(|w| w + 2).sum();
+        let mut out = String::new();
+        for line in format_row(&self.header) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out.push_str(&"=".repeat(total));
+        out.push('\n');
+        for (index, row) in cells.iter().enumerate() {
+            let lines = format_row(row);
+            let multiline = lines.len() > 1;
+            for line in lines {
+                out.push_str(&line);
+                out.push('\n');
+            }
+            if multiline && index + 1 < cells.len() {
+                out.push_str(&"-".repeat(total));
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// The rows as `LABEL.COLUMN value` lines, like Python `LabelValueTable`.
+    ///
+    /// The label of a row is made of the values of the label columns joined with `.`;
+    /// they default to the superkey, or to the first column.
+    pub fn label_value_table(&self, labels: &[&str]) -> Result<String, String> {
+        let labels: Vec<String> = if !labels.is_empty() {
+            labels.iter().map(|s| s.to_string()).collect()
+        } else if !self.superkey.is_empty() {
+            self.superkey.clone()
+        } else {
+            self.header.iter().take(1).cloned().collect()
+        };
+        for label in &labels {
+            self.column_index(label)?;
+        }
+        let mut out = format!("# LABEL {}\n", labels.join("."));
+        for row in self.rows() {
+            let prefix: Vec<String> = labels
+                .iter()
+                .filter_map(|label| row.get(label))
+                .map(value_text)
+                .collect();
+            let prefix = prefix.join(".");
+            for (name, value) in row.iter() {
+                if !labels.contains(name) {
+                    out.push_str(&format!("{}.{} {}\n", prefix, name, value_text(value)));
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl From<ParsedOutput> for TextTable {
+    fn from(out: ParsedOutput) -> Self {
+        TextTable::from_records(out.header, &out.records)
+    }
+}
+
+/// The header and the rows as comma separated lines, like `str()` of a Python table.
+impl fmt::Display for TextTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.header.join(", "))?;
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(value_text).collect();
+            writeln!(f, "{}", cells.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+//! Typed values, an opt-in template extension.
+//!
+//! A comment directive gives the type of a Value:
+//!
+//! ```text
+//! # @type MTU int
+//! Value MTU (\d+)
+//! ```
+//!
+//! Python TextFSM sees an ordinary comment. The types are `int`, `float`, `ipv4`,
+//! `ipv6`, `mac`, `bool` and `str`; any other name refers to a custom converter
+//! registered in `Converters`.
+
+use crate::{TextFSM, TextFSMError, Value};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+pub const TYPE_DIRECTIVE: &str = "@type";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType {
+    Str,
+    Int,
+    Float,
+    Ipv4,
+    Ipv6,
+    Mac,
+    Bool,
+    /// Name of a converter registered in `Converters`
+    Custom(String),
+}
+
+impl ValueType {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "str" => ValueType::Str,
+            "int" => ValueType::Int,
+            "float" => ValueType::Float,
+            "ipv4" => ValueType::Ipv4,
+            "ipv6" => ValueType::Ipv6,
+            "mac" => ValueType::Mac,
+            "bool" => ValueType::Bool,
+            x => ValueType::Custom(x.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let octets: Vec<String> = self.0.iter().map(|b| format!("{:02x}", b)).collect();
+        write!(f, "{}", octets.join(":"))
+    }
+}
+
+impl Serialize for MacAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse the usual notations: `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff`,
+/// `aabb.ccdd.eeff` and `aabbccddeeff`.
+pub fn parse_mac(text: &str) -> Result<MacAddr, String> {
+    let digits: String = text
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid MAC address '{}'", text));
+    }
+    let mut octets = [0u8; 6];
+    for (i, octet) in octets.iter_mut().enumerate() {
+        *octet = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16)
+            .map_err(|_| format!("invalid MAC address '{}'", text))?;
+    }
+    Ok(MacAddr(octets))
+}
+
+pub(crate) fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum TypedValue {
+    /// The value did not capture anything
+    Empty,
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Mac(MacAddr),
+    Bool(bool),
+    List(Vec<TypedValue>),
+}
+
+pub type Converter = Arc<dyn Fn(&str) -> Result<TypedValue, String> + Send + Sync>;
+
+/// Custom converters, by the type name used in the templates.
+#[derive(Clone, Default)]
+pub struct Converters {
+    custom: HashMap<String, Converter>,
+}
+
+impl fmt::Debug for Converters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.custom.keys().collect();
+        names.sort();
+        f.debug_struct("Converters")
+            .field("custom", &names)
+            .finish()
+    }
+}
+
+impl Converters {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn register<F>(&mut self, name: &str, converter: F)
+    where
+        F: Fn(&str) -> Result<TypedValue, String> + Send + Sync + 'static,
+    {
+        self.custom.insert(name.to_string(), Arc::new(converter));
+    }
+
+    pub fn convert(&self, typ: &ValueType, text: &str) -> Result<TypedValue, String> {
+        if text.is_empty() {
+            return Ok(TypedValue::Empty);
+        }
+        match typ {
+            ValueType::Str => Ok(TypedValue::Str(text.to_string())),
+            ValueType::Int => text
+                .trim()
+                .parse()
+                .map(TypedValue::Int)
+                .map_err(|_| format!("invalid integer '{}'", text)),
+            ValueType::Float => text
+                .trim()
+                .parse()
+                .map(TypedValue::Float)
+                .map_err(|_| format!("invalid number '{}'", text)),
+            ValueType::Ipv4 => text
+                .trim()
+                .parse()
+                .map(TypedValue::Ipv4)
+                .map_err(|_| format!("invalid IPv4 address '{}'", text)),
+            ValueType::Ipv6 => text
+                .trim()
+                .parse()
+                .map(TypedValue::Ipv6)
+                .map_err(|_| format!("invalid IPv6 address '{}'", text)),
+            ValueType::Mac => parse_mac(text).map(TypedValue::Mac),
+            ValueType::Bool => parse_bool(text)
+                .map(TypedValue::Bool)
+                .ok_or_else(|| format!("invalid bool '{}'", text)),
+            ValueType::Custom(name) => match self.custom.get(name) {
+                Some(converter) => converter(text),
+                None => Err(format!("no converter registered for type '{}'", name)),
+            },
+        }
+    }
+
+    pub fn convert_value(&self, typ: &ValueType, value: &Value) -> Result<TypedValue, String> {
+        match value {
+            Value::Single(s) => self.convert(typ, s),
+            Value::List(l) => l
+                .iter()
+                .map(|item| self.convert(typ, item))
+                .collect::<Result<Vec<TypedValue>, String>>()
+                .map(TypedValue::List),
+        }
+    }
+}
+
+/// Read the `# @type NAME TYPE` directives of a template.
+pub fn parse_type_directives(template: &str) -> Result<Vec<(String, ValueType)>, String> {
+    let mut types = vec![];
+    for (lineno, line) in template.lines().enumerate() {
+        let Some(comment) = line.trim_start().strip_prefix('#') else {
+            continue;
+        };
+        let mut words = comment.split_whitespace();
+        if words.next() != Some(TYPE_DIRECTIVE) {
+            continue;
+        }
+        match (words.next(), words.next(), words.next()) {
+            (Some(name), Some(typ), None) => {
+                types.push((name.to_string(), ValueType::from_name(typ)));
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected '# {} NAME TYPE'",
+                    lineno + 1,
+                    TYPE_DIRECTIVE
+                ))
+            }
+        }
+    }
+    Ok(types)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypedRecord {
+    #[serde(flatten)]
+    pub fields: HashMap<String, TypedValue>,
+    /// Input line where the record was emitted
+    #[serde(skip)]
+    pub line: usize,
+}
+
+/// A value of a record that could not be converted to its type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    /// Index of the record
+    pub record: usize,
+    /// Input line where the record was emitted
+    pub line: usize,
+    pub value_name: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: record {}: value {}: {}",
+            self.line, self.record, self.value_name, self.message
+        )
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Failure of `TextFSM::parse_string_typed`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedParseError {
+    /// The parse itself failed, like reaching the Error state
+    Parse(TextFSMError),
+    /// Values could not be converted, one error per value
+    Conversion(Vec<ConversionError>),
+}
+
+impl From<TextFSMError> for TypedParseError {
+    fn from(e: TextFSMError) -> Self {
+        TypedParseError::Parse(e)
+    }
+}
+
+impl fmt::Display for TypedParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedParseError::Parse(e) => write!(f, "{}", e),
+            TypedParseError::Conversion(errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypedParseError {}
+
+impl TextFSM {
+    /// Parse the input and convert the values to the types annotated in the template.
+    ///
+    /// The values without a type are kept as strings. All the conversion failures
+    /// are reported, one per value.
+    pub fn parse_string_typed(
+        &mut self,
+        input: &str,
+        converters: &Converters,
+    ) -> Result<Vec<TypedRecord>, TypedParseError> {
+        let records = self.try_parse_string(input, None)?;
+        let mut typed_records = vec![];
+        let mut errors = vec![];
+        for (index, rec) in records.iter().enumerate() {
+            let line = self.record_lines.get(index).copied().unwrap_or(0);
+            let mut fields = HashMap::new();
+            for (name, value) in rec.iter() {
+                let typ = self.parser.value_types.get(name).unwrap_or(&ValueType::Str);
+                match converters.convert_value(typ, value) {
+                    Ok(typed) => {
+                        fields.insert(name.clone(), typed);
+                    }
+                    Err(message) => errors.push(ConversionError {
+                        record: index,
+                        line,
+                        value_name: name.clone(),
+                        message,
+                    }),
+                }
+            }
+            typed_records.push(TypedRecord { fields, line });
+        }
+        if errors.is_empty() {
+            Ok(typed_records)
+        } else {
+            errors.sort_by(|a, b| (a.record, &a.value_name).cmp(&(b.record, &b.value_name)));
+            Err(TypedParseError::Conversion(errors))
+        }
+    }
+}
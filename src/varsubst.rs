@@ -1,3 +1,4 @@
+use log::warn;
 use pest::error::Error;
 pub use pest::iterators::Pair;
 pub use pest::Parser;
@@ -27,7 +28,7 @@ impl VariableParser {
                         out.push(ParseChunk::DollarDollar);
                     }
                     Rule::end_dollar => {
-                        println!("WARNING: unescaped dollar in the end of line '{}'", &input);
+                        warn!("unescaped dollar in the end of line '{}'", &input);
                         out.push(ParseChunk::DollarDollar);
                     }
                     Rule::variable_name => {
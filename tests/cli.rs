@@ -0,0 +1,227 @@
+use std::process::{Command, Output};
+
+fn textfsm(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_textfsm"))
+        .args(args)
+        .output()
+        .expect("failed to run textfsm")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_parse_json() {
+    let output = textfsm(&[
+        "parse",
+        "--format",
+        "json",
+        "tests/data/templates/cisco_ios_show_version.textfsm",
+        "tests/data/raw/cisco_ios_show_version.raw",
+    ]);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["VERSION"], "15.0(2)SE4");
+    // values are in the order of their declaration
+    let keys: Vec<&String> = json[0].as_object().unwrap().keys().collect();
+    assert_eq!(keys, vec!["VERSION", "HOSTNAME", "UPTIME"]);
+}
+
+#[test]
+fn test_parse_cmd_csv() {
+    let output = textfsm(&[
+        "parse-cmd",
+        "--index",
+        "tests/data/templates/index",
+        "--platform",
+        "cisco_ios",
+        "--command",
+        "sh int",
+        "-f",
+        "csv",
+        "tests/data/raw/cisco_ios_show_interfaces.raw",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "INTERFACE,LINK_STATUS,DESCRIPTION\nGigabitEthernet0/1,up,uplink\nGigabitEthernet0/2,down,\n"
+    );
+}
+
+#[test]
+fn test_parse_cmd_metadata() {
+    let output = textfsm(&[
+        "parse-cmd",
+        "--index",
+        "tests/data/templates/index",
+        "--platform",
+        "cisco_ios",
+        "--command",
+        "sh int",
+        "--meta",
+        "HOSTNAME=router1",
+        "-f",
+        "csv",
+        "tests/data/raw/cisco_ios_show_interfaces.raw",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output).lines().take(2).collect::<Vec<_>>(),
+        vec![
+            "HOSTNAME,INTERFACE,LINK_STATUS,DESCRIPTION",
+            "router1,GigabitEthernet0/1,up,uplink"
+        ]
+    );
+}
+
+#[test]
+fn test_parse_cmd_no_template_suggests() {
+    let output = textfsm(&[
+        "parse-cmd",
+        "--index",
+        "tests/data/templates/index",
+        "--platform",
+        "cisco_ios",
+        "--command",
+        "sho vresion",
+        "tests/data/raw/cisco_ios_show_version.raw",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("did you mean 'show version'?"));
+}
+
+#[test]
+fn test_exit_codes() {
+    let output = textfsm(&[
+        "parse",
+        "tests/data/cli/error_state.textfsm",
+        "tests/data/raw/cisco_ios_show_interfaces.raw",
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stdout(&output).is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unexpected description"));
+    assert!(stderr.contains("Input line 2"));
+
+    let output = textfsm(&[
+        "parse",
+        "--lenient",
+        "tests/data/cli/error_state.textfsm",
+        "tests/data/raw/cisco_ios_show_interfaces.raw",
+    ]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("GigabitEthernet0/2"));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("textfsm: line 2: state Start: unexpected description"));
+
+    let output = textfsm(&[
+        "parse",
+        "tests/data/bad/broken.textfsm",
+        "tests/data/raw/cisco_ios_show_version.raw",
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+
+    // a rule going to an undefined state is a template error
+    let output = textfsm(&[
+        "parse",
+        "tests/data/bad/undefined_state.textfsm",
+        "tests/data/raw/cisco_ios_show_version.raw",
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("State 'Nowhere' not found"));
+
+    let output = textfsm(&["validate", "--index", "tests/data/bad/index"]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = textfsm(&["validate", "--index", "tests/data/templates/index"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_parse_encoding() {
+    let args = [
+        "parse",
+        "--format",
+        "csv",
+        "tests/data/cli/description.textfsm",
+        "tests/data/cli/latin1.raw",
+    ];
+    let output = textfsm(&args);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = textfsm(&[&args[..], &["--encoding", "latin1"]].concat());
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("café uplink"));
+
+    let output = textfsm(&[&args[..], &["--encoding", "utf8-lossy"]].concat());
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 2: invalid UTF-8 [e9]"));
+}
+
+#[test]
+fn test_detect() {
+    let output = textfsm(&[
+        "detect",
+        "--index",
+        "tests/data/templates/index",
+        "tests/data/raw/cisco_ios_show_version.raw",
+    ]);
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.contains("cisco_ios_show_version.textfsm  records: 1"));
+}
+
+#[test]
+fn test_fmt() {
+    let output = textfsm(&["fmt", "tests/data/cli/unformatted.textfsm"]);
+    assert!(output.status.success());
+    let expected = std::fs::read_to_string("tests/data/cli/formatted.textfsm").unwrap();
+    assert_eq!(stdout(&output), expected);
+
+    let output = textfsm(&["fmt", "--check", "tests/data/cli/unformatted.textfsm"]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = textfsm(&["fmt", "--check", "tests/data/cli/formatted.textfsm"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_test_subcommand() {
+    let output = textfsm(&[
+        "test",
+        "tests/data/templates/cisco_ios_show_version.textfsm",
+        "tests/data/raw/cisco_ios_show_version.raw",
+        "tests/data/raw/cisco_ios_show_version.yml",
+    ]);
+    assert!(output.status.success());
+    let output = textfsm(&[
+        "test",
+        "tests/data/templates/cisco_ios_show_version.textfsm",
+        "tests/data/raw/cisco_ios_show_version.raw",
+        "tests/data/cli/wrong_version.yml",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_dot() {
+    let output = textfsm(&["dot", "tests/data/cli/formatted.textfsm"]);
+    assert!(output.status.success());
+    let dot = stdout(&output);
+    assert!(dot.starts_with("digraph textfsm {"));
+    assert!(dot.contains("\"Start\" -> \"Ports\""));
+    assert!(dot.contains("\"Start\" -> \"Error\""));
+}
+
+#[test]
+fn test_schema() {
+    let output = textfsm(&[
+        "schema",
+        "tests/data/templates/cisco_ios_show_interfaces.textfsm",
+    ]);
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["title"], "cisco_ios_show_interfaces");
+    assert_eq!(schema["x-textfsm-keys"][0], "INTERFACE");
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use textfsm_rs::*;
+
+const INDEX: &str = "tests/data/templates/index";
+
+fn attributes(platform: &str, command: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    attrs.insert("Platform".to_string(), platform.to_string());
+    attrs.insert("Command".to_string(), command.to_string());
+    attrs
+}
+
+fn single(s: &str) -> Value {
+    Value::Single(s.to_string())
+}
+
+#[test]
+fn test_parse_cmd_single_template() {
+    let cli_table = CliTable::from_file(INDEX);
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let out = cli_table
+        .parse_cmd(&text, &attributes("cisco_ios", "sh ver"))
+        .unwrap();
+    assert_eq!(out.header, vec!["VERSION", "HOSTNAME", "UPTIME"]);
+    assert_eq!(out.records.len(), 1);
+    assert_eq!(out.records[0].get("VERSION"), Some(&single("15.0(2)SE4")));
+    assert_eq!(out.records[0].get("HOSTNAME"), Some(&single("router1")));
+}
+
+#[test]
+fn test_parse_cmd_merges_templates_by_key() {
+    let cli_table = CliTable::from_file(INDEX);
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_interfaces.raw").unwrap();
+    let out = cli_table
+        .parse_cmd(&text, &attributes("cisco_ios", "show interfaces"))
+        .unwrap();
+    assert_eq!(out.header, vec!["INTERFACE", "LINK_STATUS", "DESCRIPTION"]);
+    assert_eq!(out.records.len(), 2);
+    assert_eq!(out.records[0].get("LINK_STATUS"), Some(&single("up")));
+    assert_eq!(out.records[0].get("DESCRIPTION"), Some(&single("uplink")));
+    assert_eq!(out.records[1].get("LINK_STATUS"), Some(&single("down")));
+    assert_eq!(out.records[1].get("DESCRIPTION"), Some(&single("")));
+}
+
+fn merged(command: &str, raw: &str) -> ParsedOutput {
+    let cli_table = CliTable::from_file("tests/data/merge/index");
+    let text = std::fs::read_to_string(raw).unwrap();
+    cli_table
+        .parse_cmd(&text, &attributes("test", command))
+        .unwrap()
+}
+
+fn column(out: &ParsedOutput, name: &str) -> Vec<Value> {
+    out.records
+        .iter()
+        .map(|rec| rec.get(name).unwrap().clone())
+        .collect()
+}
+
+#[test]
+fn test_parse_cmd_merges_templates_without_key_by_position() {
+    let out = merged("show interfaces", "tests/data/merge/interfaces.raw");
+    assert_eq!(out.header, vec!["NAME", "STATUS", "DESC"]);
+    assert_eq!(
+        column(&out, "NAME"),
+        vec![single("eth0"), single("eth1"), single("eth2")]
+    );
+    assert_eq!(
+        column(&out, "STATUS"),
+        vec![single("up"), single("down"), single("up")]
+    );
+    assert_eq!(
+        column(&out, "DESC"),
+        vec![single("one"), single("two"), single("three")]
+    );
+}
+
+#[test]
+fn test_parse_cmd_merges_templates_on_all_keys() {
+    let out = merged("show vrf interfaces", "tests/data/merge/vrf_interfaces.raw");
+    assert_eq!(out.header, vec!["VRF", "NAME", "ADDR", "DESC"]);
+    assert_eq!(
+        column(&out, "VRF"),
+        vec![single("A"), single("A"), single("B")]
+    );
+    assert_eq!(
+        column(&out, "NAME"),
+        vec![single("eth0"), single("eth1"), single("eth0")]
+    );
+    assert_eq!(
+        column(&out, "DESC"),
+        vec![single(""), single("two"), single("three")]
+    );
+}
+
+#[test]
+fn test_parse_cmd_caches_templates() {
+    let cli_table = CliTable::from_file(INDEX);
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let first = cli_table
+        .parse_cmd(&text, &attributes("cisco_ios", "show version"))
+        .unwrap();
+    assert_eq!(cli_table.template_cache.len(), 1);
+    let second = cli_table
+        .parse_cmd(&text, &attributes("cisco_ios", "show version"))
+        .unwrap();
+    assert_eq!(cli_table.template_cache.len(), 1);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_parse_cmd_no_template() {
+    let cli_table = CliTable::from_file(INDEX);
+    assert!(cli_table
+        .parse_cmd("", &attributes("cisco_ios", "show clock"))
+        .is_err());
+    assert!(cli_table.parse_cmd("", &HashMap::new()).is_err());
+}
+
+#[test]
+fn test_parse_cmd_shared_between_threads() {
+    let cli_table = std::sync::Arc::new(CliTable::from_file(INDEX));
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let cli_table = cli_table.clone();
+            let text = text.clone();
+            std::thread::spawn(move || {
+                cli_table
+                    .parse_cmd(&text, &attributes("cisco_ios", "show version"))
+                    .unwrap()
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().records.len(), 1);
+    }
+    assert_eq!(cli_table.template_cache.len(), 1);
+}
+
+const INDEX_ATTRIBUTES: &str = "tests/data/templates/index_attributes";
+
+#[test]
+fn test_lookup_by_hostname() {
+    let cli_table = CliTable::from_file(INDEX_ATTRIBUTES);
+    let mut attrs = attributes("cisco_ios", "show version");
+    attrs.insert("Hostname".to_string(), "lab-sw1".to_string());
+    let (_dir, row) = cli_table.get_template_for_attributes(&attrs).unwrap();
+    assert_eq!(row.templates, vec!["cisco_ios_show_version_lab.textfsm"]);
+
+    attrs.insert("Hostname".to_string(), "core-sw1".to_string());
+    let (_dir, row) = cli_table.get_template_for_attributes(&attrs).unwrap();
+    assert_eq!(row.templates, vec!["cisco_ios_show_version.textfsm"]);
+    assert_eq!(row.columns.get("Vendor"), Some(&"cisco".to_string()));
+}
+
+#[test]
+fn test_lookup_by_arbitrary_column() {
+    let cli_table = CliTable::from_file(INDEX_ATTRIBUTES);
+    assert_eq!(
+        cli_table.tables[0].header,
+        vec!["Template", "Hostname", "Vendor", "Platform", "Command"]
+    );
+    let mut attrs = attributes("cisco_ios", "sh ver");
+    attrs.insert("Vendor".to_string(), "juniper".to_string());
+    assert!(cli_table.get_template_for_attributes(&attrs).is_none());
+
+    // empty cells match anything
+    let mut attrs = attributes("cisco_ios", "sh int");
+    attrs.insert("Vendor".to_string(), "juniper".to_string());
+    let (_dir, row) = cli_table.get_template_for_attributes(&attrs).unwrap();
+    assert_eq!(row.templates, vec!["cisco_ios_show_interfaces.textfsm"]);
+
+    // attributes without a column are ignored
+    let mut attrs = attributes("cisco_ios", "sh int");
+    attrs.insert("Model".to_string(), "C2960".to_string());
+    assert!(cli_table.get_template_for_attributes(&attrs).is_some());
+}
+
+#[test]
+fn test_lookup_anchored_at_start() {
+    let cli_table = CliTable::from_file(INDEX_ATTRIBUTES);
+    assert!(cli_table
+        .get_template_for_attributes(&attributes("cisco_ios", "do show version"))
+        .is_none());
+    assert!(cli_table
+        .get_template_for_attributes(&attributes("cisco_ios_telnet", "show version"))
+        .is_some());
+}
+
+#[test]
+fn test_parse_cmd_with_hostname() {
+    let cli_table = CliTable::from_file(INDEX_ATTRIBUTES);
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let mut attrs = attributes("cisco_ios", "show version");
+    attrs.insert("Hostname".to_string(), "lab-sw1".to_string());
+    let out = cli_table.parse_cmd(&text, &attrs).unwrap();
+    assert_eq!(out.header, vec!["VERSION"]);
+}
+
+const LOCAL_INDEX: &str = "tests/data/local/index";
+
+#[test]
+fn test_multiple_index_files_precedence() {
+    let cli_table = CliTable::from_files(&[LOCAL_INDEX, INDEX]);
+    assert_eq!(cli_table.tables.len(), 2);
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+
+    // the local index shadows the upstream one
+    let out = cli_table
+        .parse_cmd(&text, &attributes("cisco_ios", "show version"))
+        .unwrap();
+    assert_eq!(out.header, vec!["VERSION", "IMAGE"]);
+    assert_eq!(
+        out.records[0].get("IMAGE"),
+        Some(&single("C2960-LANBASEK9-M"))
+    );
+
+    // the local index extends the upstream one
+    let out = cli_table
+        .parse_cmd(
+            "*10:15:32.123 UTC Mon Mar 1 2021\n",
+            &attributes("cisco_ios", "sh clock"),
+        )
+        .unwrap();
+    assert_eq!(out.records[0].get("TIMEZONE"), Some(&single("UTC")));
+
+    // the commands not in the local index come from the upstream one
+    let (dir, row) = cli_table
+        .get_template_for_attributes(&attributes("cisco_ios", "show interfaces"))
+        .unwrap();
+    assert_eq!(dir, "tests/data/templates");
+    assert_eq!(row.templates.len(), 2);
+
+    // reversing the order gives the upstream templates the precedence
+    let cli_table = CliTable::from_files(&[INDEX, LOCAL_INDEX]);
+    let out = cli_table
+        .parse_cmd(&text, &attributes("cisco_ios", "show version"))
+        .unwrap();
+    assert_eq!(out.header, vec!["VERSION", "HOSTNAME", "UPTIME"]);
+}
+
+#[test]
+fn test_validate_good_index() {
+    let cli_table = CliTable::from_files(&[LOCAL_INDEX, INDEX]);
+    for report in cli_table.validate() {
+        assert!(report.issues.is_empty(), "{}", report);
+    }
+}
+
+#[test]
+fn test_validate_reports_problems() {
+    use textfsm_rs::cli_table::*;
+
+    let table = ParsedCliTable::try_from_file("tests/data/bad/index").unwrap();
+    let report = table.validate();
+    assert!(!report.is_ok());
+    let issues: Vec<(Option<usize>, &IndexIssueKind)> = report
+        .issues
+        .iter()
+        .map(|i| (i.row_index, &i.kind))
+        .collect();
+    assert_eq!(issues.len(), 8, "{}", report);
+    assert_eq!(
+        issues[0],
+        (None, &IndexIssueKind::UnknownColumn("Region".to_string()))
+    );
+    assert!(matches!(
+        issues[1],
+        (Some(1), IndexIssueKind::MissingTemplate(_))
+    ));
+    assert!(matches!(
+        issues[2],
+        (Some(2), IndexIssueKind::TemplateError { .. })
+    ));
+    assert!(matches!(
+        issues[3],
+        (Some(2), IndexIssueKind::BadCompletion(_))
+    ));
+    assert_eq!(
+        issues[4],
+        (Some(3), &IndexIssueKind::ShadowedRow { by_row_index: 0 })
+    );
+    assert_eq!(
+        issues[5],
+        (
+            Some(4),
+            &IndexIssueKind::DuplicateRow { first_row_index: 0 }
+        )
+    );
+    assert!(matches!(
+        issues[6],
+        (Some(5), IndexIssueKind::BadRegex { .. })
+    ));
+    // compiles, but a rule goes to an undefined state
+    match issues[7] {
+        (Some(7), IndexIssueKind::TemplateError { template, message }) => {
+            assert!(template.ends_with("undefined_state.textfsm"));
+            assert_eq!(message, "State 'Nowhere' not found, used in state 'Start'");
+        }
+        other => panic!("unexpected issue {:?}", other),
+    }
+    assert_eq!(report.errors().count(), 5);
+    assert_eq!(report.warnings().count(), 3);
+}
+
+#[test]
+fn test_validate_unloadable_index() {
+    assert!(cli_table::ParsedCliTable::try_from_file("tests/data/no-such-index").is_err());
+}
+
+#[test]
+fn test_try_from_files_reports_bad_regex() {
+    assert!(CliTable::try_from_files(&["tests/data/no-such-index"]).is_err());
+    let err = CliTable::try_from_files(&["tests/data/bad/index"])
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.starts_with("tests/data/bad/index: row 3: bad regex in column Command: "),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_list_platforms_and_commands() {
+    let cli_table = CliTable::from_files(&[LOCAL_INDEX, INDEX]);
+    assert_eq!(cli_table.platforms(), vec!["cisco_ios"]);
+    assert_eq!(
+        cli_table.commands_for_platform("cisco_ios"),
+        vec!["show version", "show clock", "show interfaces"]
+    );
+    assert!(cli_table.commands_for_platform("juniper_junos").is_empty());
+    assert_eq!(
+        CliTable::canonical_command("sh[[ow]] ip int[[erface]] br[[ief]]"),
+        "show ip interface brief"
+    );
+}
+
+#[test]
+fn test_complete_command() {
+    let cli_table = CliTable::from_file(INDEX);
+    assert_eq!(
+        cli_table.complete_command("cisco_ios", "sh ver"),
+        Some("show version".to_string())
+    );
+    assert_eq!(
+        cli_table.complete_command("cisco_ios", "sh int"),
+        Some("show interfaces".to_string())
+    );
+    assert_eq!(cli_table.complete_command("cisco_ios", "sh clock"), None);
+}
+
+#[test]
+fn test_templates_for_command() {
+    let cli_table = CliTable::from_files(&[LOCAL_INDEX, INDEX]);
+    let templates = cli_table
+        .templates_for_command("cisco_ios", "show interfaces")
+        .unwrap();
+    assert_eq!(
+        templates,
+        vec![
+            std::path::Path::new("tests/data/templates/cisco_ios_show_interfaces.textfsm"),
+            std::path::Path::new(
+                "tests/data/templates/cisco_ios_show_interfaces_description.textfsm"
+            ),
+        ]
+    );
+    let templates = cli_table
+        .templates_for_command("cisco_ios", "show version")
+        .unwrap();
+    assert_eq!(
+        templates,
+        vec![std::path::Path::new(
+            "tests/data/local/local_show_version.textfsm"
+        )]
+    );
+}
+
+#[test]
+fn test_suggest_commands() {
+    let cli_table = CliTable::from_files(&[LOCAL_INDEX, INDEX]);
+    assert!(cli_table
+        .get_template_for_attributes(&attributes("cisco_ios", "sho vresion"))
+        .is_none());
+    let suggestions = cli_table.suggest("cisco_ios", "sho vresion", 3);
+    assert!(suggestions.platforms.is_empty());
+    assert_eq!(suggestions.commands.len(), 1);
+    assert_eq!(suggestions.commands[0].value, "show version");
+    assert_eq!(suggestions.commands[0].distance, 2);
+
+    let suggestions = cli_table.suggest("cisco_ios", "show int status", 3);
+    assert_eq!(suggestions.commands[0].value, "show interfaces");
+}
+
+#[test]
+fn test_suggest_platforms() {
+    let cli_table = CliTable::from_file(INDEX);
+    let suggestions = cli_table.suggest("cisco_iso", "show version", 3);
+    assert_eq!(suggestions.platforms[0].value, "cisco_ios");
+    assert_eq!(suggestions.commands[0].value, "show version");
+    assert_eq!(suggestions.commands[0].distance, 0);
+
+    let suggestions = cli_table.suggest("juniper_junos", "show version", 3);
+    assert_eq!(suggestions, Default::default());
+}
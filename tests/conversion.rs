@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use textfsm_rs::conversion::snake_case;
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"Value Key INTERFACE_NAME (\S+)
+Value LinkStatus (up|down)
+
+Start
+  ^${INTERFACE_NAME} is ${LinkStatus} -> Record
+"#;
+
+fn parse(conversion: DataRecordConversion) -> Vec<DataRecord> {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let mut textfsm = TextFSM::from_parser(parser);
+    textfsm.parse_string("eth0 is up\n", Some(conversion))
+}
+
+fn keys(records: &[DataRecord]) -> Vec<String> {
+    let mut keys: Vec<String> = records[0].keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn test_snake_case() {
+    assert_eq!(snake_case("INTERFACE_NAME"), "interface_name");
+    assert_eq!(snake_case("LinkStatus"), "link_status");
+    assert_eq!(snake_case("IPAddress"), "ip_address");
+    assert_eq!(snake_case("vlan10Id"), "vlan10_id");
+    assert_eq!(snake_case("in-octets"), "in_octets");
+}
+
+#[test]
+fn test_builtin_conversions() {
+    assert_eq!(
+        keys(&parse(DataRecordConversion::LowercaseKeys)),
+        vec!["interface_name", "linkstatus"]
+    );
+    assert_eq!(
+        keys(&parse(DataRecordConversion::UppercaseKeys)),
+        vec!["INTERFACE_NAME", "LINKSTATUS"]
+    );
+    assert_eq!(
+        keys(&parse(DataRecordConversion::SnakeCaseKeys)),
+        vec!["interface_name", "link_status"]
+    );
+    let renames = HashMap::from([("LinkStatus".to_string(), "STATUS".to_string())]);
+    assert_eq!(
+        keys(&parse(DataRecordConversion::RenameKeys(renames))),
+        vec!["INTERFACE_NAME", "STATUS"]
+    );
+    let records = parse(DataRecordConversion::PrefixKeys(
+        "show_interfaces.".to_string(),
+    ));
+    assert_eq!(
+        keys(&records),
+        vec![
+            "show_interfaces.INTERFACE_NAME",
+            "show_interfaces.LinkStatus"
+        ]
+    );
+    // the record key is not affected
+    let unconverted = parse(DataRecordConversion::Chain(vec![]));
+    assert!(records[0].record_key.is_some());
+    assert_eq!(records[0].record_key, unconverted[0].record_key);
+}
+
+#[test]
+fn test_custom_and_chained_conversions() {
+    let reverse: Arc<dyn KeyConverter> =
+        Arc::new(|key: &str| key.chars().rev().collect::<String>());
+    let conversion = DataRecordConversion::Chain(vec![
+        DataRecordConversion::SnakeCaseKeys,
+        DataRecordConversion::Custom(reverse),
+    ]);
+    assert_eq!(
+        keys(&parse(conversion)),
+        vec!["eman_ecafretni", "sutats_knil"]
+    );
+}
+
+#[test]
+fn test_convert_parsed_output() {
+    let cli_table = CliTable::from_file("tests/data/templates/index");
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let mut attributes = HashMap::new();
+    attributes.insert("Platform".to_string(), "cisco_ios".to_string());
+    attributes.insert("Command".to_string(), "show version".to_string());
+    let out = cli_table
+        .parse_cmd(&text, &attributes)
+        .unwrap()
+        .convert_keys(&DataRecordConversion::LowercaseKeys);
+    assert_eq!(out.header, vec!["version", "hostname", "uptime"]);
+    assert!(out.records[0].get("hostname").is_some());
+}
@@ -0,0 +1,136 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use textfsm_rs::de::{from_records, from_value};
+use textfsm_rs::*;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Interface {
+    name: String,
+    address: Option<IpAddr>,
+    mtu: Option<u32>,
+    enabled: bool,
+    vlans: Vec<u16>,
+}
+
+fn record(fields: &[(&str, Value)]) -> DataRecord {
+    let mut rec = DataRecord::new();
+    for (name, value) in fields {
+        rec.fields.insert(name.to_string(), value.clone());
+    }
+    rec
+}
+
+fn single(s: &str) -> Value {
+    Value::Single(s.to_string())
+}
+
+fn list(items: &[&str]) -> Value {
+    Value::List(items.iter().map(|s| s.to_string()).collect())
+}
+
+#[test]
+fn test_deserialize_struct_with_coercions() {
+    let records = vec![
+        record(&[
+            ("NAME", single("eth0")),
+            ("ADDRESS", single("10.0.0.1")),
+            ("MTU", single("1500")),
+            ("ENABLED", single("yes")),
+            ("VLANS", list(&["10", "20"])),
+            ("SPEED", single("1000")),
+        ]),
+        record(&[
+            ("NAME", single("eth1")),
+            ("ADDRESS", single("")),
+            ("ENABLED", single("false")),
+            ("VLANS", single("30")),
+        ]),
+    ];
+    let interfaces: Vec<Interface> = from_records(&records).unwrap();
+    assert_eq!(
+        interfaces,
+        vec![
+            Interface {
+                name: "eth0".to_string(),
+                address: Some("10.0.0.1".parse().unwrap()),
+                mtu: Some(1500),
+                enabled: true,
+                vlans: vec![10, 20],
+            },
+            Interface {
+                name: "eth1".to_string(),
+                address: None,
+                mtu: None,
+                enabled: false,
+                vlans: vec![30],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_deserialize_error_names_record_and_field() {
+    let records = vec![
+        record(&[
+            ("NAME", single("eth0")),
+            ("ENABLED", single("true")),
+            ("VLANS", list(&[])),
+        ]),
+        record(&[
+            ("NAME", single("eth1")),
+            ("MTU", single("jumbo")),
+            ("ENABLED", single("true")),
+            ("VLANS", list(&[])),
+        ]),
+    ];
+    let err = from_records::<Interface>(&records).unwrap_err();
+    assert_eq!(err.record, Some(1));
+    assert_eq!(err.field.as_deref(), Some("MTU"));
+    assert_eq!(
+        err.to_string(),
+        "record 1, field MTU: invalid integer 'jumbo'"
+    );
+
+    let missing = from_records::<Interface>(&[record(&[("NAME", single("eth0"))])]).unwrap_err();
+    assert_eq!(missing.record, Some(0));
+    assert!(missing.message.contains("enabled"));
+}
+
+#[test]
+fn test_deserialize_parsed_output() {
+    #[derive(Debug, Deserialize)]
+    struct Version {
+        #[serde(rename = "VERSION")]
+        release: String,
+        hostname: String,
+    }
+    let cli_table = CliTable::from_file("tests/data/templates/index");
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let mut attributes = HashMap::new();
+    attributes.insert("Platform".to_string(), "cisco_ios".to_string());
+    attributes.insert("Command".to_string(), "show version".to_string());
+    let out = cli_table.parse_cmd(&text, &attributes).unwrap();
+    let versions: Vec<Version> = out.deserialize_as().unwrap();
+    assert_eq!(versions[0].release, "15.0(2)SE4");
+    assert_eq!(versions[0].hostname, "router1");
+
+    let map: HashMap<String, String> = out.records[0].deserialize_as().unwrap();
+    assert_eq!(map["HOSTNAME"], "router1");
+}
+
+#[test]
+fn test_deserialize_value() {
+    assert_eq!(from_value::<f64>(&single(" 2.5 ")).unwrap(), 2.5);
+    assert_eq!(from_value::<Option<i32>>(&list(&[])).unwrap(), None);
+    assert_eq!(from_value::<i32>(&list(&["7"])).unwrap(), 7);
+    assert!(from_value::<i32>(&list(&["7", "8"])).is_err());
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        Up,
+        Down,
+    }
+    assert_eq!(from_value::<Status>(&single("down")).unwrap(), Status::Down);
+}
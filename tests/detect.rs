@@ -0,0 +1,67 @@
+use std::path::Path;
+use textfsm_rs::*;
+
+const INDEX: &str = "tests/data/templates/index";
+
+fn best(scores: &[detect::TemplateScore]) -> &str {
+    scores[0].template.file_name().unwrap().to_str().unwrap()
+}
+
+#[test]
+fn test_detect_template() {
+    let cli_table = CliTable::from_file(INDEX);
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_version.raw").unwrap();
+    let scores = cli_table.detect_template(&text, None);
+    assert_eq!(scores.len(), 3);
+    assert_eq!(best(&scores), "cisco_ios_show_version.textfsm");
+    assert_eq!(scores[0].records, 1);
+    assert_eq!(scores[0].value_fill, 1.0);
+    assert!(scores[1..].iter().all(|s| s.score < scores[0].score));
+
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_interfaces.raw").unwrap();
+    let scores = cli_table.detect_template(&text, Some("cisco_ios"));
+    assert_eq!(
+        best(&scores),
+        "cisco_ios_show_interfaces_description.textfsm"
+    );
+    assert_eq!(scores[0].line_coverage, 1.0);
+    assert_eq!(scores[0].command, "sh[[ow]] int[[erfaces]]");
+    assert_eq!(
+        scores[1].template,
+        Path::new("tests/data/templates/cisco_ios_show_interfaces.textfsm")
+    );
+    assert!(scores[1].line_coverage < 1.0);
+    assert_eq!(scores[2].score, 0.0);
+
+    assert!(cli_table
+        .detect_template(&text, Some("juniper_junos"))
+        .is_empty());
+}
+
+#[test]
+fn test_error_transitions_lower_the_score() {
+    let dir = std::env::temp_dir().join(format!("textfsm-detect-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        "tests/data/cli/error_state.textfsm",
+        dir.join("error.textfsm"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("plain.textfsm"),
+        "Value INTERFACE (\\S+)\n\nStart\n  ^${INTERFACE}\\s+is -> Record\n  ^\\s+Description\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("index"),
+        "Template, Platform, Command\n\nerror.textfsm, cisco_ios, show interfaces\nplain.textfsm, cisco_ios, show interfaces\n",
+    )
+    .unwrap();
+    let cli_table = CliTable::from_file(dir.join("index").to_str().unwrap());
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_interfaces.raw").unwrap();
+    let scores = cli_table.detect_template(&text, None);
+    assert_eq!(best(&scores), "plain.textfsm");
+    assert_eq!(scores[1].error_transitions, 1);
+    assert_eq!(scores[1].line_coverage, scores[0].line_coverage);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
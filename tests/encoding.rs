@@ -0,0 +1,61 @@
+use textfsm_rs::encoding::{decode, Encoding, InvalidSequence};
+use textfsm_rs::*;
+
+const TEMPLATE: &str = "tests/data/cli/description.textfsm";
+const INPUT: &[u8] = b"GigabitEthernet0/1 is up\n  Description: caf\xe9 uplink\n";
+
+#[test]
+fn test_decode_reports_invalid_sequences() {
+    let decoded = decode(INPUT, Encoding::Utf8Lossy);
+    assert!(decoded.text.contains("caf\u{fffd} uplink"));
+    assert_eq!(
+        decoded.invalid_sequences,
+        vec![InvalidSequence {
+            line: 2,
+            offset: 43,
+            bytes: vec![0xe9],
+        }]
+    );
+
+    let decoded = decode(INPUT, Encoding::Utf8OrLatin1);
+    assert!(decoded.text.contains("café uplink"));
+    assert_eq!(decoded.invalid_sequences.len(), 1);
+
+    let decoded = decode(INPUT, Encoding::Latin1);
+    assert!(decoded.text.contains("café uplink"));
+    assert!(decoded.invalid_sequences.is_empty());
+}
+
+#[test]
+fn test_decode_keeps_valid_utf8() {
+    let decoded = decode("naïve\n".as_bytes(), Encoding::Utf8OrLatin1);
+    assert_eq!(decoded.text, "naïve\n");
+    assert!(decoded.invalid_sequences.is_empty());
+}
+
+#[test]
+fn test_parse_bytes() {
+    let mut textfsm = TextFSM::from_file(TEMPLATE);
+    let parsed = textfsm
+        .try_parse_bytes(INPUT, Encoding::Utf8OrLatin1, None)
+        .unwrap();
+    assert_eq!(
+        parsed.records[0].get("DESCRIPTION"),
+        Some(&Value::Single("café uplink".to_string()))
+    );
+    assert_eq!(
+        parsed.invalid_sequences,
+        vec![InvalidSequence {
+            line: 2,
+            offset: 43,
+            bytes: vec![0xe9],
+        }]
+    );
+
+    let mut textfsm = TextFSM::from_file(TEMPLATE);
+    let parsed = textfsm
+        .try_parse_bytes(INPUT, Encoding::Latin1, None)
+        .unwrap();
+    assert_eq!(parsed.records.len(), 1);
+    assert!(parsed.invalid_sequences.is_empty());
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use textfsm_rs::*;
+
+const ERROR_TEMPLATE: &str = "tests/data/cli/error_state.textfsm";
+const INPUT: &str = "tests/data/raw/cisco_ios_show_interfaces.raw";
+
+#[test]
+fn test_error_state_returns_partial_records() {
+    let mut textfsm = TextFSM::from_file(ERROR_TEMPLATE);
+    let err = textfsm
+        .try_parse_file(INPUT, Some(DataRecordConversion::LowercaseKeys))
+        .unwrap_err();
+    match err {
+        TextFSMError::ErrorState {
+            ref message,
+            line_number,
+            ref line,
+            ref state,
+            ..
+        } => {
+            assert_eq!(message.as_deref(), Some("unexpected description"));
+            assert_eq!(line_number, 2);
+            assert_eq!(line, "  Description: uplink");
+            assert_eq!(state, "Start");
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(err.records().len(), 1);
+    assert_eq!(
+        err.records()[0].get("interface"),
+        Some(&Value::Single("GigabitEthernet0/1".to_string()))
+    );
+    assert_eq!(
+        err.to_string(),
+        "Error state reached in state Start: unexpected description. Input line 2: '  Description: uplink'"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error state reached")]
+fn test_parse_string_still_panics() {
+    let mut textfsm = TextFSM::from_file(ERROR_TEMPLATE);
+    let input = std::fs::read_to_string(INPUT).unwrap();
+    textfsm.parse_string(&input, None);
+}
+
+#[test]
+fn test_template_state_errors() {
+    let err =
+        TextFSMParser::from_string("Value A (\\S+)\n\nOther\n  ^${A} -> Record\n").unwrap_err();
+    assert_eq!(err, "Missing state 'Start'");
+    let err =
+        TextFSMParser::from_string("Value A (\\S+)\n\nStart\n  ^${A} -> Nowhere\n").unwrap_err();
+    assert_eq!(err, "State 'Nowhere' not found, used in state 'Start'");
+    // End and EOF are always defined
+    assert!(
+        TextFSMParser::from_string("Value A (\\S+)\n\nStart\n  ^${A} -> EOF\n  ^x -> End\n")
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_missing_input_file() {
+    let mut textfsm = TextFSM::from_file(ERROR_TEMPLATE);
+    let err = textfsm
+        .try_parse_file("tests/data/raw/missing.raw", None)
+        .unwrap_err();
+    assert!(matches!(err, TextFSMError::Io { .. }));
+    assert!(err.records().is_empty());
+}
+
+#[test]
+fn test_parse_cmd_returns_error_state() {
+    let dir = std::env::temp_dir().join(format!("textfsm-rs-error-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(ERROR_TEMPLATE, dir.join("error_state.textfsm")).unwrap();
+    std::fs::write(
+        dir.join("index"),
+        "Template, Platform, Command\n\nerror_state.textfsm, cisco_ios, sh[[ow]] int[[erfaces]]\n",
+    )
+    .unwrap();
+    let cli_table = CliTable::from_file(dir.join("index").to_str().unwrap());
+    let mut attributes = HashMap::new();
+    attributes.insert("Platform".to_string(), "cisco_ios".to_string());
+    attributes.insert("Command".to_string(), "show interfaces".to_string());
+    let text = std::fs::read_to_string(INPUT).unwrap();
+    let err = cli_table.parse_cmd(&text, &attributes).unwrap_err();
+    let err = err.downcast::<TextFSMError>().unwrap();
+    assert_eq!(err.records().len(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,47 @@
+use textfsm_rs::*;
+
+const ERROR_TEMPLATE: &str = "tests/data/cli/error_state.textfsm";
+const INPUT: &str = "tests/data/raw/cisco_ios_show_interfaces.raw";
+
+#[test]
+fn test_lenient_goes_past_error_state() {
+    let mut textfsm = TextFSM::from_file(ERROR_TEMPLATE);
+    let input = std::fs::read_to_string(INPUT).unwrap();
+    let out = textfsm.parse_string_lenient(&input, Some(DataRecordConversion::LowercaseKeys));
+    assert_eq!(
+        out.diagnostics,
+        vec![lenient::Diagnostic {
+            line_number: 2,
+            line: "  Description: uplink".to_string(),
+            state: "Start".to_string(),
+            message: Some("unexpected description".to_string()),
+        }]
+    );
+    let interfaces: Vec<_> = out.records.iter().map(|r| r.get("interface")).collect();
+    assert_eq!(
+        interfaces,
+        vec![
+            Some(&Value::Single("GigabitEthernet0/1".to_string())),
+            Some(&Value::Single("GigabitEthernet0/2".to_string())),
+        ]
+    );
+    assert!(out.unmatched_lines.is_empty());
+}
+
+#[test]
+fn test_lenient_collects_unmatched_lines() {
+    let template = "Value INTERFACE (\\S+)\n\nStart\n  ^${INTERFACE}\\s+is -> Record\n";
+    let mut textfsm = TextFSM::from_parser(TextFSMParser::from_string(template).unwrap());
+    let input = std::fs::read_to_string(INPUT).unwrap();
+    let out = textfsm.parse_string_lenient(&input, None);
+    assert!(out.diagnostics.is_empty());
+    assert_eq!(out.records.len(), 2);
+    assert_eq!(
+        out.unmatched_lines,
+        vec![lenient::UnmatchedLine {
+            line_number: 2,
+            line: "  Description: uplink".to_string(),
+            state: "Start".to_string(),
+        }]
+    );
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+use textfsm_rs::limits::{CancelToken, ParseLimits};
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"Value NAME (\S+)
+
+Start
+  ^interface ${NAME} -> Record
+"#;
+
+const INPUT: &str = "interface eth0\ninterface eth1\ninterface eth2\n";
+
+fn textfsm(limits: ParseLimits) -> TextFSM {
+    TextFSM::from_parser(TextFSMParser::from_string(TEMPLATE).unwrap())
+        .with_limits(limits)
+        .unwrap()
+}
+
+#[test]
+fn test_input_and_line_size_limits() {
+    let err = textfsm(ParseLimits {
+        max_input_size: Some(16),
+        ..Default::default()
+    })
+    .try_parse_string(INPUT, None)
+    .unwrap_err();
+    assert_eq!(
+        err,
+        TextFSMError::InputTooLarge {
+            size: INPUT.len(),
+            limit: 16
+        }
+    );
+
+    let input = format!("interface eth0\ninterface {}\n", "x".repeat(100));
+    let err = textfsm(ParseLimits {
+        max_line_length: Some(64),
+        ..Default::default()
+    })
+    .try_parse_string(&input, None)
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        TextFSMError::LineTooLong {
+            length: 110,
+            limit: 64,
+            line_number: 2,
+            ..
+        }
+    ));
+    assert_eq!(err.records().len(), 1);
+}
+
+#[test]
+fn test_record_limit_keeps_the_records_within_it() {
+    let err = textfsm(ParseLimits {
+        max_records: Some(2),
+        ..Default::default()
+    })
+    .try_parse_string(INPUT, None)
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        TextFSMError::TooManyRecords {
+            limit: 2,
+            line_number: 3,
+            ..
+        }
+    ));
+    assert_eq!(err.records().len(), 2);
+    assert_eq!(err.to_string(), "More than 2 records at input line 3");
+}
+
+#[test]
+fn test_cancel_and_timeout() {
+    let token = CancelToken::new();
+    let mut fsm = textfsm(Default::default()).with_cancel_token(token.clone());
+    token.cancel();
+    let err = fsm.try_parse_string(INPUT, None).unwrap_err();
+    assert!(matches!(
+        err,
+        TextFSMError::Cancelled { line_number: 1, .. }
+    ));
+
+    let err = textfsm(ParseLimits {
+        timeout: Some(Duration::ZERO),
+        ..Default::default()
+    })
+    .try_parse_string(INPUT, None)
+    .unwrap_err();
+    assert!(matches!(err, TextFSMError::Timeout { line_number: 1, .. }));
+}
+
+#[test]
+fn test_backtrack_limit() {
+    let template = r#"Value NAME (\S+)
+
+Start
+  ^interface ((?!x)\w)*${NAME} -> Record
+"#;
+    let input = format!("interface {}!\n", "a".repeat(30));
+    let parser = TextFSMParser::from_string(template).unwrap();
+
+    let mut fsm = TextFSM::from_parser(parser.clone());
+    assert_eq!(fsm.try_parse_string(&input, None).unwrap().len(), 1);
+
+    let mut fsm = TextFSM::from_parser(parser)
+        .with_limits(ParseLimits {
+            backtrack_limit: Some(10),
+            ..Default::default()
+        })
+        .unwrap();
+    let err = fsm.try_parse_string(&input, None).unwrap_err();
+    assert!(matches!(
+        err,
+        TextFSMError::BacktrackLimitExceeded { line_number: 1, .. }
+    ));
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use textfsm_rs::metadata::{Metadata, MetadataConflict};
+use textfsm_rs::*;
+
+const TEMPLATE: &str = "tests/data/templates/cisco_ios_show_version.textfsm";
+const INPUT: &str = "tests/data/raw/cisco_ios_show_version.raw";
+
+fn single(s: &str) -> Value {
+    Value::Single(s.to_string())
+}
+
+#[test]
+fn test_metadata_in_records_and_header() {
+    let metadata = Metadata::new()
+        .platform("cisco_ios")
+        .collected_at("2026-10-19T08:00:00Z")
+        .with("SITE", "paris");
+    let mut textfsm = TextFSM::from_file(TEMPLATE)
+        .with_metadata(metadata)
+        .unwrap();
+    let records = textfsm.parse_file(INPUT, Some(DataRecordConversion::LowercaseKeys));
+    assert_eq!(
+        textfsm.header(),
+        vec![
+            "PLATFORM",
+            "COLLECTED_AT",
+            "SITE",
+            "VERSION",
+            "HOSTNAME",
+            "UPTIME"
+        ]
+    );
+    assert_eq!(records[0].get("site"), Some(&single("paris")));
+    assert_eq!(
+        records[0].get("collected_at"),
+        Some(&single("2026-10-19T08:00:00Z"))
+    );
+    assert_eq!(records[0].get("version"), Some(&single("15.0(2)SE4")));
+}
+
+#[test]
+fn test_metadata_conflicts() {
+    let metadata = Metadata::new().hostname("device1");
+    let err = TextFSM::from_file(TEMPLATE)
+        .with_metadata(metadata.clone())
+        .unwrap_err();
+    assert_eq!(err, "metadata column 'HOSTNAME' is a Value of the template");
+
+    let parse = |conflict: MetadataConflict| {
+        let mut textfsm = TextFSM::from_file(TEMPLATE)
+            .with_metadata(metadata.clone().on_conflict(conflict))
+            .unwrap();
+        let records = textfsm.parse_file(INPUT, None);
+        (textfsm.header(), records[0].clone())
+    };
+
+    let (header, rec) = parse(MetadataConflict::KeepTemplate);
+    assert_eq!(header, vec!["VERSION", "HOSTNAME", "UPTIME"]);
+    assert_eq!(rec.get("HOSTNAME"), Some(&single("router1")));
+
+    let (header, rec) = parse(MetadataConflict::Overwrite);
+    assert_eq!(header, vec!["VERSION", "HOSTNAME", "UPTIME"]);
+    assert_eq!(rec.get("HOSTNAME"), Some(&single("device1")));
+
+    let (header, rec) = parse(MetadataConflict::Prefix("META_".to_string()));
+    assert_eq!(
+        header,
+        vec!["META_HOSTNAME", "VERSION", "HOSTNAME", "UPTIME"]
+    );
+    assert_eq!(rec.get("META_HOSTNAME"), Some(&single("device1")));
+    assert_eq!(rec.get("HOSTNAME"), Some(&single("router1")));
+}
+
+#[test]
+fn test_parse_cmd_with_metadata() {
+    let cli_table = CliTable::from_file("tests/data/templates/index");
+    let attributes: HashMap<String, String> = [
+        ("Platform".to_string(), "cisco_ios".to_string()),
+        ("Command".to_string(), "sh int".to_string()),
+    ]
+    .into_iter()
+    .collect();
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_interfaces.raw").unwrap();
+    let metadata = Metadata::new().hostname("router1").command("sh int");
+    let out = cli_table
+        .parse_cmd_with_metadata(&text, &attributes, &metadata)
+        .unwrap();
+    assert_eq!(
+        out.header,
+        vec![
+            "HOSTNAME",
+            "COMMAND",
+            "INTERFACE",
+            "LINK_STATUS",
+            "DESCRIPTION"
+        ]
+    );
+    assert!(out
+        .records
+        .iter()
+        .all(|rec| rec.get("HOSTNAME") == Some(&single("router1"))));
+}
@@ -0,0 +1,71 @@
+use textfsm_rs::output::{CsvOptions, JsonShape};
+use textfsm_rs::*;
+
+fn sample_output() -> ParsedOutput {
+    let parser = TextFSMParser::from_string(
+        "Value NAME (\\S+)\nValue List ADDR (\\S+)\nValue MTU (\\d+)\n\nStart\n  ^interface ${NAME}\n  ^ address ${ADDR}\n  ^ mtu ${MTU}\n  ^! -> Record\n",
+    )
+    .unwrap();
+    let mut textfsm = TextFSM::from_parser(parser);
+    let records = textfsm.parse_string(
+        "interface eth0\n address 10.0.0.1\n address 10.0.0.2\n mtu 1500\n!\ninterface lo\n!\n",
+        None,
+    );
+    ParsedOutput {
+        header: textfsm.header(),
+        records,
+    }
+}
+
+#[test]
+fn test_csv_joins_lists() {
+    let out = sample_output();
+    assert_eq!(
+        out.to_csv(&CsvOptions::default()),
+        "NAME,ADDR,MTU\neth0,\"10.0.0.1,10.0.0.2\",1500\nlo,,\n"
+    );
+    let options = CsvOptions {
+        list_separator: " ".to_string(),
+        write_header: false,
+    };
+    assert_eq!(out.to_csv(&options), "eth0,10.0.0.1 10.0.0.2,1500\nlo,,\n");
+}
+
+#[test]
+fn test_json_keeps_declaration_order() {
+    let out = sample_output();
+    let objects = out.to_json(JsonShape::Objects);
+    assert_eq!(
+        objects.to_string(),
+        r#"[{"NAME":"eth0","ADDR":["10.0.0.1","10.0.0.2"],"MTU":"1500"},{"NAME":"lo","ADDR":[],"MTU":""}]"#
+    );
+    let arrays = out.to_json(JsonShape::Arrays);
+    assert_eq!(
+        arrays.to_string(),
+        r#"[["NAME","ADDR","MTU"],["eth0",["10.0.0.1","10.0.0.2"],"1500"],["lo",[],""]]"#
+    );
+}
+
+#[test]
+fn test_parsed_sample_yaml() {
+    let out = sample_output();
+    let yaml = serde_yaml::to_string(&out.to_parsed_sample_yaml()).unwrap();
+    assert_eq!(
+        yaml,
+        "parsed_sample:\n- name: eth0\n  addr:\n  - 10.0.0.1\n  - 10.0.0.2\n  mtu: '1500'\n- name: lo\n  addr: []\n  mtu: ''\n"
+    );
+}
+
+#[test]
+fn test_text_table_aligns_columns() {
+    let out = sample_output();
+    assert_eq!(
+        out.to_text_table(80),
+        concat!(
+            " NAME  ADDR                MTU  \n",
+            "================================\n",
+            " eth0  10.0.0.1, 10.0.0.2  1500 \n",
+            " lo                             \n",
+        )
+    );
+}
@@ -0,0 +1,110 @@
+use textfsm_rs::pipeline::transforms::{self, expand_interface_name};
+use textfsm_rs::pipeline::Pipeline;
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"Value INTERFACE (\S+)
+Value DESCRIPTION (.*)
+Value VLANS (\S+)
+
+Start
+  ^${INTERFACE}\s+vlans\s+${VLANS}\s+${DESCRIPTION}$$ -> Record
+"#;
+
+const INPUT: &str = "Gi0/1 vlans 10,20, uplink  \nTe1/0/1 vlans -- N/A\nLo0 vlans -- --\n";
+
+fn single(s: &str) -> Value {
+    Value::Single(s.to_string())
+}
+
+fn parse(pipeline: Pipeline) -> Vec<DataRecord> {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let mut textfsm = TextFSM::from_parser(parser).with_pipeline(pipeline);
+    textfsm.parse_string(INPUT, None)
+}
+
+fn cleanups() -> Pipeline {
+    Pipeline::new()
+        .with_transform(transforms::trim())
+        .with_transform(transforms::empty_if(&["N/A", "--"]))
+        .with_value_transform("INTERFACE", transforms::normalize_interface_name())
+        .with_value_transform("VLANS", transforms::split_list(","))
+}
+
+#[test]
+fn test_pipeline_transforms_values() {
+    let records = parse(cleanups());
+    assert_eq!(records.len(), 3);
+    assert_eq!(
+        records[0].get("INTERFACE"),
+        Some(&single("GigabitEthernet0/1"))
+    );
+    assert_eq!(records[0].get("DESCRIPTION"), Some(&single("uplink")));
+    assert_eq!(
+        records[0].get("VLANS"),
+        Some(&Value::List(vec!["10".to_string(), "20".to_string()]))
+    );
+    assert_eq!(
+        records[1].get("INTERFACE"),
+        Some(&single("TenGigabitEthernet1/0/1"))
+    );
+    assert_eq!(records[1].get("DESCRIPTION"), Some(&single("")));
+    assert_eq!(records[1].get("VLANS"), Some(&Value::List(vec![])));
+}
+
+#[test]
+fn test_record_transforms_compose() {
+    let drop_loopbacks = Pipeline::new().with_record_transform(|rec| match rec.get("INTERFACE") {
+        Some(Value::Single(name)) if name.starts_with("Loopback") => None,
+        _ => Some(rec),
+    });
+    let records = parse(cleanups().then(drop_loopbacks));
+    assert_eq!(records.len(), 2);
+
+    let only_names = Pipeline::new()
+        .with_values_transform(&["DESCRIPTION", "VLANS"], |_| single(""))
+        .with_record_transform(transforms::drop_empty());
+    assert_eq!(parse(only_names).len(), 3);
+}
+
+#[test]
+fn test_expand_interface_name() {
+    for (short, long) in [
+        ("Gi0/1", "GigabitEthernet0/1"),
+        ("gig1/0/24", "GigabitEthernet1/0/24"),
+        ("Fa0/1", "FastEthernet0/1"),
+        ("Tw1/0/1", "TwoGigabitEthernet1/0/1"),
+        ("Twe1/0/1", "TwentyFiveGigE1/0/1"),
+        ("Po10", "Port-channel10"),
+        ("Eth1/1", "Ethernet1/1"),
+        ("Ma0", "Management0"),
+        ("mgmt0", "mgmt0"),
+        ("GigabitEthernet0/1", "GigabitEthernet0/1"),
+        ("Null0", "Null0"),
+        ("eth", "eth"),
+    ] {
+        assert_eq!(expand_interface_name(short.to_string()), long);
+    }
+}
+
+#[test]
+fn test_pipeline_sees_fillup_values() {
+    let template = r#"Value INTERFACE (\S+)
+Value Fillup VRF (\S+)
+
+Start
+  ^interface ${INTERFACE} -> Record
+  ^vrf ${VRF}
+"#;
+    let parser = TextFSMParser::from_string(template).unwrap();
+    let pipeline = Pipeline::new().with_value_transform("VRF", |value| match value {
+        Value::Single(s) => Value::Single(s.to_uppercase()),
+        list => list,
+    });
+    let mut textfsm = TextFSM::from_parser(parser).with_pipeline(pipeline);
+    let records = textfsm.parse_string("interface Gi0/1\ninterface Gi0/2\nvrf blue\n", None);
+    let vrfs: Vec<&Value> = records.iter().filter_map(|rec| rec.get("VRF")).collect();
+    assert_eq!(
+        vrfs,
+        vec![&single("BLUE"), &single("BLUE"), &single("BLUE")]
+    );
+}
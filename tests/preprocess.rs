@@ -0,0 +1,66 @@
+use textfsm_rs::preprocess::{filters, InputFilter, Preprocessor};
+use textfsm_rs::*;
+
+#[test]
+fn test_builtin_filters() {
+    assert_eq!(filters::normalize_newlines()("a\r\nb\r\n"), "a\nb\n");
+    assert_eq!(
+        filters::strip_ansi()("\x1b[1;32mup\x1b[0m \x1b]0;title\x07down"),
+        "up down"
+    );
+    assert_eq!(filters::resolve_overwrites()("abc\x08\x08XY"), "aXY");
+    assert_eq!(filters::resolve_overwrites()("12345\rab\r"), "ab345");
+    assert_eq!(
+        filters::resolve_overwrites()(
+            "  ---- More ----\x1b[42D                                          \x1b[42DVlan1 is up"
+        ),
+        "Vlan1 is up                               "
+    );
+    assert_eq!(
+        filters::remove_pager_prompts()(" --More-- Vlan1 is up\n---(more 45%)---\n"),
+        "Vlan1 is up\n\n"
+    );
+    assert_eq!(filters::trim_trailing_whitespace()("a  \nb\t\n"), "a\nb\n");
+}
+
+#[test]
+fn test_cisco_pager_erasure() {
+    let input = "Vlan1 is up\r\n --More-- \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08          \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08Vlan2 is down\r\n";
+    assert_eq!(
+        Preprocessor::cli_session().apply(input),
+        "Vlan1 is up\nVlan2 is down\n"
+    );
+}
+
+struct Uppercase;
+
+impl InputFilter for Uppercase {
+    fn filter(&self, input: &str) -> String {
+        input.to_uppercase()
+    }
+}
+
+#[test]
+fn test_preprocessor_in_parse_session() {
+    let template = "Value NAME (\\S+)\n\nStart\n  ^INTERFACE ${NAME} -> Record\n";
+    let parser = TextFSMParser::from_string(template).unwrap();
+    let input = "\x1b[1minterface\x1b[0m eth0\r\n --More-- interface eth1\r\n";
+
+    let mut fsm = TextFSM::from_parser(parser.clone());
+    assert!(fsm.parse_string(input, None).is_empty());
+
+    let mut fsm = TextFSM::from_parser(parser)
+        .with_preprocessor(Preprocessor::cli_session().with_filter(Uppercase));
+    let names: Vec<Value> = fsm
+        .parse_string(input, None)
+        .into_iter()
+        .map(|rec| rec.get("NAME").unwrap().clone())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            Value::Single("ETH0".to_string()),
+            Value::Single("ETH1".to_string())
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+use textfsm_rs::provenance::ValueProvenance;
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"Value Filldown CHASSIS (\S+)
+Value NAME (\S+)
+Value List ADDRESSES (\S+)
+
+Start
+  ^chassis ${CHASSIS}
+  ^interface ${NAME}
+  ^  address ${ADDRESSES}
+  ^! -> Record
+"#;
+
+const INPUT: &str =
+    "chassis c1\ninterface eth0\n  address 10.0.0.1\n  address 10.0.0.2\n!\ninterface eth1\n!\n";
+
+fn parse(track: bool) -> Vec<DataRecord> {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let mut textfsm = TextFSM::from_parser(parser).with_provenance(track);
+    textfsm.parse_string(INPUT, None)
+}
+
+#[test]
+fn test_provenance_of_values() {
+    let records = parse(true);
+    let provenance = records[0].provenance().unwrap();
+    assert_eq!((provenance.first_line, provenance.last_line), (1, 5));
+    assert_eq!(
+        records[0].value_provenance("NAME").unwrap(),
+        [ValueProvenance {
+            line: 2,
+            span: 10..14,
+            state: "Start".to_string(),
+            rule_index: 1,
+        }]
+    );
+    let lines: Vec<usize> = records[0]
+        .value_provenance("ADDRESSES")
+        .unwrap()
+        .iter()
+        .map(|capture| capture.line)
+        .collect();
+    assert_eq!(lines, vec![3, 4]);
+
+    // the filldown value keeps the line where it was captured
+    let provenance = records[1].provenance().unwrap();
+    assert_eq!((provenance.first_line, provenance.last_line), (1, 7));
+    assert_eq!(records[1].value_provenance("CHASSIS").unwrap()[0].line, 1);
+    assert_eq!(records[1].value_provenance("NAME").unwrap()[0].line, 6);
+    assert!(records[1].value_provenance("ADDRESSES").is_none());
+}
+
+#[test]
+fn test_provenance_is_optional() {
+    let tracked = parse(true);
+    let untracked = parse(false);
+    assert!(untracked[0].provenance().is_none());
+    // the provenance does not change the equality nor the serialization
+    assert_eq!(tracked, untracked);
+    assert_eq!(
+        serde_json::to_value(&tracked[0]).unwrap(),
+        serde_json::to_value(&untracked[0]).unwrap()
+    );
+    let json = serde_json::to_value(tracked[0].serialize_with_provenance()).unwrap();
+    assert_eq!(json["NAME"], "eth0");
+    assert_eq!(json["_provenance"]["values"]["NAME"][0]["line"], 2);
+    assert_eq!(
+        json["_provenance"]["values"]["NAME"][0]["span"]["start"],
+        10
+    );
+}
+
+#[test]
+fn test_provenance_follows_key_conversion() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let mut textfsm = TextFSM::from_parser(parser).with_provenance(true);
+    let records = textfsm.parse_string(INPUT, Some(DataRecordConversion::LowercaseKeys));
+    assert_eq!(records[0].value_provenance("name").unwrap()[0].line, 2);
+}
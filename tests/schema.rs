@@ -0,0 +1,54 @@
+use serde_json::json;
+use textfsm_rs::schema::SchemaOptions;
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"# @type MTU int
+Value Key,Required NAME (\S+)
+Value List ADDRESSES (\S+)
+Value MTU (\d+)
+
+Start
+  ^interface ${NAME}
+  ^ address ${ADDRESSES}
+  ^ mtu ${MTU}
+  ^! -> Record
+"#;
+
+#[test]
+fn test_json_schema() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let schema = parser.json_schema(&SchemaOptions::default());
+    assert_eq!(
+        schema,
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "NAME": {"type": "string", "x-textfsm-key": true},
+                "ADDRESSES": {"type": "array", "items": {"type": "string"}},
+                "MTU": {"type": "string"},
+            },
+            "required": ["NAME", "ADDRESSES", "MTU"],
+            "x-textfsm-keys": ["NAME"],
+        })
+    );
+    // properties are in the order of the declaration
+    let names: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+    assert_eq!(names, vec!["NAME", "ADDRESSES", "MTU"]);
+}
+
+#[test]
+fn test_json_schema_typed() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let options = SchemaOptions {
+        title: Some("interfaces".to_string()),
+        typed: true,
+    };
+    let schema = parser.json_schema(&options);
+    assert_eq!(schema["title"], "interfaces");
+    assert_eq!(
+        schema["properties"]["MTU"],
+        json!({"anyOf": [{"type": "integer"}, {"type": "null"}]})
+    );
+    assert_eq!(schema["properties"]["NAME"]["type"], "string");
+}
@@ -0,0 +1,59 @@
+use textfsm_rs::session::SessionSplitter;
+use textfsm_rs::*;
+
+const SESSION: &str = "tests/data/session/cisco_ios_session.log";
+
+#[test]
+fn test_split_by_prompt() {
+    let transcript = std::fs::read_to_string(SESSION).unwrap();
+    let commands = SessionSplitter::default().split(&transcript);
+    let names: Vec<(&str, &str, usize)> = commands
+        .iter()
+        .map(|c| (c.hostname.as_str(), c.command.as_str(), c.line))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("router1", "terminal length 0", 2),
+            ("router1", "show version", 3),
+            ("router1", "sh int", 7),
+            ("router1", "show ip int brief", 11),
+        ]
+    );
+    assert!(commands[0].output.is_empty());
+    assert_eq!(
+        commands[2].output,
+        std::fs::read_to_string("tests/data/raw/cisco_ios_show_interfaces.raw").unwrap()
+    );
+}
+
+#[test]
+fn test_custom_prompt() {
+    let splitter = SessionSplitter::new(r"^\$ (?P<command>.*)$").unwrap();
+    let commands = splitter.split("$ uname\nLinux\n$ true\n");
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[0].output, "Linux\n");
+    assert_eq!(commands[1].hostname, "");
+
+    assert!(SessionSplitter::new(r"^\$ (.*)$").is_err());
+}
+
+#[test]
+fn test_parse_session() {
+    let cli_table = CliTable::from_file("tests/data/templates/index");
+    let transcript = std::fs::read_to_string(SESSION).unwrap();
+    let session = cli_table.parse_session(&transcript, "cisco_ios", &SessionSplitter::default());
+    assert!(session.failed.is_empty());
+    let parsed: Vec<(&str, usize)> = session
+        .parsed
+        .iter()
+        .map(|p| (p.command.command.as_str(), p.parsed.records.len()))
+        .collect();
+    assert_eq!(parsed, vec![("show version", 1), ("sh int", 2)]);
+    let no_template: Vec<&str> = session
+        .no_template
+        .iter()
+        .map(|c| c.command.as_str())
+        .collect();
+    assert_eq!(no_template, vec!["terminal length 0", "show ip int brief"]);
+}
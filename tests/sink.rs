@@ -0,0 +1,52 @@
+use std::ops::ControlFlow;
+use std::sync::mpsc;
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"Value NAME (\S+)
+
+Start
+  ^interface ${NAME} -> Record
+"#;
+
+const INPUT: &str = "interface eth0\ninterface eth1\ninterface eth2\n";
+
+fn textfsm() -> TextFSM {
+    TextFSM::from_parser(TextFSMParser::from_string(TEMPLATE).unwrap())
+}
+
+#[test]
+fn test_sink_receives_records_without_accumulating() {
+    let (tx, rx) = mpsc::channel();
+    let mut fsm = textfsm();
+    let flow = fsm.parse_string_into::<(), _>(INPUT, None, |rec| {
+        tx.send(rec).unwrap();
+        ControlFlow::Continue(())
+    });
+    assert_eq!(flow, Ok(ControlFlow::Continue(())));
+    assert!(fsm.records.is_empty());
+    let names: Vec<Value> = rx
+        .try_iter()
+        .map(|rec| rec.get("NAME").unwrap().clone())
+        .collect();
+    assert_eq!(
+        names,
+        ["eth0", "eth1", "eth2"].map(|s| Value::Single(s.to_string()))
+    );
+}
+
+#[test]
+fn test_sink_stops_the_parse() {
+    let mut seen = vec![];
+    let mut fsm = textfsm();
+    let flow = fsm.parse_string_into(INPUT, Some(DataRecordConversion::LowercaseKeys), |rec| {
+        seen.push(rec);
+        if seen.len() == 2 {
+            ControlFlow::Break("enough")
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(flow, Ok(ControlFlow::Break("enough")));
+    assert_eq!(seen.len(), 2);
+    assert!(seen[1].get("name").is_some());
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use textfsm_rs::limits::ParseLimits;
+use textfsm_rs::*;
+
+fn write_template(name: &str, value_name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "textfsm-rs-{}-{}.textfsm",
+        std::process::id(),
+        name
+    ));
+    let template = format!(
+        "Value {} (\\S+)\n\nStart\n  ^${{{}}} -> Record\n",
+        value_name, value_name
+    );
+    std::fs::write(&path, template).unwrap();
+    path
+}
+
+#[test]
+fn test_cache_reuses_compiled_template() {
+    let path = write_template("reuse", "NAME");
+    let cache = TemplateCache::new();
+    let first = cache.get(&path).unwrap();
+    let second = cache.get(&path).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(cache.len(), 1);
+    cache.invalidate(&path);
+    assert!(cache.is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cache_mtime_invalidation() {
+    let path = write_template("mtime", "NAME");
+    let cache = TemplateCache::new().with_mtime_check(true);
+    assert_eq!(cache.get(&path).unwrap().value_names, vec!["NAME"]);
+
+    // make sure the modification time changes even on coarse-grained filesystems
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+    std::fs::write(
+        &path,
+        "Value OTHER (\\S+)\n\nStart\n  ^${OTHER} -> Record\n",
+    )
+    .unwrap();
+    file.set_modified(later).unwrap();
+
+    assert_eq!(cache.get(&path).unwrap().value_names, vec!["OTHER"]);
+    assert_eq!(cache.len(), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cache_reports_errors() {
+    let cache = TemplateCache::new();
+    assert!(cache
+        .get(std::path::Path::new("tests/data/no-such.textfsm"))
+        .is_err());
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_sessions_share_cached_template() {
+    let path = write_template("shared", "NAME");
+    let cache = TemplateCache::new();
+    let parser = cache.get(&path).unwrap();
+    let fsm = TextFSM::from_parser(parser.clone());
+    assert!(Arc::ptr_eq(&fsm.parser, &parser));
+
+    // a backtrack limit recompiles a copy, the cached template is left as is
+    let limited = TextFSM::from_parser(parser.clone())
+        .with_limits(ParseLimits {
+            backtrack_limit: Some(10),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(!Arc::ptr_eq(&limited.parser, &parser));
+    assert!(Arc::ptr_eq(&cache.get(&path).unwrap(), &parser));
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use textfsm_rs::*;
+
+fn single(s: &str) -> Value {
+    Value::Single(s.to_string())
+}
+
+fn table(header: &[&str], rows: &[&[&str]]) -> TextTable {
+    let mut table = TextTable::new(header.iter().map(|s| s.to_string()).collect());
+    for row in rows {
+        table
+            .append(row.iter().map(|s| single(s)).collect())
+            .unwrap();
+    }
+    table
+}
+
+fn column(table: &TextTable, name: &str) -> Vec<String> {
+    table
+        .rows()
+        .map(|row| match row.get(name) {
+            Some(Value::Single(s)) => s.clone(),
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_append_checks_row_length() {
+    let mut t = table(&["A", "B"], &[&["1", "2"]]);
+    assert!(t.append(vec![single("3")]).is_err());
+    t.add_column("C", single("x")).unwrap();
+    assert_eq!(t.get(0, "C"), Some(&single("x")));
+    assert!(t.add_column("C", single("y")).is_err());
+    assert_eq!(t.remove(0), vec![single("1"), single("2"), single("x")]);
+    assert!(t.is_empty());
+}
+
+#[test]
+fn test_sort_numeric_and_superkey() {
+    let mut t = table(
+        &["NAME", "MTU"],
+        &[&["eth1", "9000"], &["eth0", "1500"], &["lo", "65536"]],
+    );
+    t.sort();
+    assert_eq!(column(&t, "NAME"), vec!["eth0", "eth1", "lo"]);
+    t.set_superkey(vec!["MTU".to_string()]).unwrap();
+    t.sort();
+    assert_eq!(column(&t, "MTU"), vec!["1500", "9000", "65536"]);
+    assert_eq!(t.key_value(2), Some(vec![&single("65536")]));
+    t.sort_by_columns(&["NAME"], true).unwrap();
+    assert_eq!(column(&t, "NAME"), vec!["lo", "eth1", "eth0"]);
+    assert!(t.set_superkey(vec!["SPEED".to_string()]).is_err());
+}
+
+#[test]
+fn test_sort_mixed_numbers_and_text() {
+    let mut t = table(
+        &["MTU"],
+        &[&["auto"], &["9000"], &[""], &["1500"], &["NaN"], &["10"]],
+    );
+    t.sort();
+    assert_eq!(
+        column(&t, "MTU"),
+        vec!["10", "1500", "9000", "NaN", "", "auto"]
+    );
+}
+
+#[test]
+fn test_filter() {
+    let t = table(&["A", "B"], &[&["1", ""], &["", ""], &["3", "4"]]);
+    assert_eq!(t.filter_empty().len(), 2);
+    let filtered = t.filter(|row| row.get("B") == Some(&single("4")));
+    assert_eq!(column(&filtered, "A"), vec!["3"]);
+}
+
+#[test]
+fn test_formatted_table() {
+    let t = table(&["LSP", "Name"], &[&["col1", "col2"], &["col1", "col2"]]);
+    assert_eq!(
+        t.formatted_table(80),
+        " LSP   Name \n============\n col1  col2 \n col1  col2 \n"
+    );
+}
+
+#[test]
+fn test_formatted_table_wraps() {
+    let t = table(
+        &["NAME", "DESCRIPTION"],
+        &[&["eth0", "link to the core router"], &["eth1", "spare"]],
+    );
+    assert_eq!(
+        t.formatted_table(19),
+        concat!(
+            " NAME  DESCRIPTION \n",
+            "===================\n",
+            " eth0  link to the \n",
+            "       core router \n",
+            "-------------------\n",
+            " eth1  spare       \n",
+        )
+    );
+}
+
+#[test]
+fn test_label_value_table() {
+    let mut t = table(&["NAME", "MTU", "STATE"], &[&["eth0", "1500", "up"]]);
+    assert_eq!(
+        t.label_value_table(&[]).unwrap(),
+        "# LABEL NAME\neth0.MTU 1500\neth0.STATE up\n"
+    );
+    t.set_superkey(vec!["NAME".to_string(), "STATE".to_string()])
+        .unwrap();
+    assert_eq!(
+        t.label_value_table(&[]).unwrap(),
+        "# LABEL NAME.STATE\neth0.up.MTU 1500\n"
+    );
+    assert!(t.label_value_table(&["SPEED"]).is_err());
+}
+
+#[test]
+fn test_display() {
+    let t = table(&["A", "B"], &[&["1", "2"]]);
+    assert_eq!(t.to_string(), "A, B\n1, 2\n");
+}
+
+#[test]
+fn test_parse_cmd_table_uses_keys() {
+    let cli_table = CliTable::from_file("tests/data/templates/index");
+    let text = std::fs::read_to_string("tests/data/raw/cisco_ios_show_interfaces.raw").unwrap();
+    let mut attributes = HashMap::new();
+    attributes.insert("Platform".to_string(), "cisco_ios".to_string());
+    attributes.insert("Command".to_string(), "show interfaces".to_string());
+    let t = cli_table.parse_cmd_table(&text, &attributes).unwrap();
+    assert_eq!(t.header(), ["INTERFACE", "LINK_STATUS", "DESCRIPTION"]);
+    assert_eq!(t.superkey(), ["INTERFACE"]);
+    assert_eq!(t.len(), 2);
+    assert_eq!(t.get(0, "DESCRIPTION"), Some(&single("uplink")));
+}
@@ -0,0 +1,133 @@
+use textfsm_rs::typed::{parse_mac, Converters, MacAddr, TypedParseError, TypedValue, ValueType};
+use textfsm_rs::*;
+
+const TEMPLATE: &str = r#"# @type MTU int
+# @type ADDRESS ipv4
+# @type MAC mac
+# @type ENABLED bool
+# @type SPEED speed
+Value NAME (\S+)
+Value MTU (\S+)
+Value ADDRESS (\S*)
+Value MAC (\S+)
+Value ENABLED (\S+)
+Value SPEED (\S+)
+
+Start
+  ^interface ${NAME} mtu ${MTU} address ${ADDRESS} mac ${MAC} enabled ${ENABLED} speed ${SPEED} -> Record
+"#;
+
+fn converters() -> Converters {
+    let mut converters = Converters::new();
+    converters.register("speed", |text| match text.strip_suffix('G') {
+        Some(gigabits) => gigabits
+            .parse::<i64>()
+            .map(|g| TypedValue::Int(g * 1000))
+            .map_err(|e| e.to_string()),
+        None => Err(format!("unknown speed '{}'", text)),
+    });
+    converters
+}
+
+#[test]
+fn test_type_directives() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    assert_eq!(parser.value_types.len(), 5);
+    assert_eq!(parser.value_types["MTU"], ValueType::Int);
+    assert_eq!(
+        parser.value_types["SPEED"],
+        ValueType::Custom("speed".to_string())
+    );
+    assert!(!parser.value_types.contains_key("NAME"));
+
+    let err = TextFSMParser::from_string("# @type NOPE int\nValue A (\\S+)\n\nStart\n  ^${A}\n")
+        .unwrap_err();
+    assert!(err.contains("NOPE"));
+}
+
+#[test]
+fn test_parse_typed() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let mut textfsm = TextFSM::from_parser(parser);
+    let records = textfsm
+        .parse_string_typed(
+            "interface eth0 mtu 1500 address 10.0.0.1 mac 0011.2233.4455 enabled yes speed 10G\n",
+            &converters(),
+        )
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    let rec = &records[0];
+    assert_eq!(rec.line, 1);
+    assert_eq!(rec.fields["NAME"], TypedValue::Str("eth0".to_string()));
+    assert_eq!(rec.fields["MTU"], TypedValue::Int(1500));
+    assert_eq!(
+        rec.fields["ADDRESS"],
+        TypedValue::Ipv4("10.0.0.1".parse().unwrap())
+    );
+    assert_eq!(
+        rec.fields["MAC"],
+        TypedValue::Mac(MacAddr([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]))
+    );
+    assert_eq!(rec.fields["ENABLED"], TypedValue::Bool(true));
+    assert_eq!(rec.fields["SPEED"], TypedValue::Int(10000));
+    assert_eq!(
+        serde_json::to_value(rec).unwrap()["MAC"],
+        serde_json::json!("00:11:22:33:44:55")
+    );
+}
+
+#[test]
+fn test_conversion_errors_have_line_numbers() {
+    let parser = TextFSMParser::from_string(TEMPLATE).unwrap();
+    let mut textfsm = TextFSM::from_parser(parser);
+    let err = textfsm
+        .parse_string_typed(
+            concat!(
+                "interface eth0 mtu 1500 address 10.0.0.1 mac 0011.2233.4455 enabled yes speed 10G\n",
+                "! comment\n",
+                "interface eth1 mtu big address  mac 0011.2233.4455 enabled no speed fast\n",
+            ),
+            &converters(),
+        )
+        .unwrap_err();
+    let TypedParseError::Conversion(errors) = err else {
+        panic!("unexpected error {:?}", err);
+    };
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "line 3: record 1: value MTU: invalid integer 'big'",
+            "line 3: record 1: value SPEED: unknown speed 'fast'",
+        ]
+    );
+}
+
+#[test]
+fn test_error_state_is_returned() {
+    let template =
+        "# @type MTU int\nValue MTU (\\d+)\n\nStart\n  ^mtu ${MTU} -> Record\n  ^bad -> Error\n";
+    let mut textfsm = TextFSM::from_parser(TextFSMParser::from_string(template).unwrap());
+    let err = textfsm
+        .parse_string_typed("mtu 1500\nbad\n", &converters())
+        .unwrap_err();
+    match err {
+        TypedParseError::Parse(e) => assert_eq!(e.records().len(), 1),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_mac_notations() {
+    let mac = MacAddr([0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+    for text in [
+        "aa:bb:cc:dd:ee:ff",
+        "AA-BB-CC-DD-EE-FF",
+        "aabb.ccdd.eeff",
+        "aabbccddeeff",
+    ] {
+        assert_eq!(parse_mac(text), Ok(mac));
+    }
+    assert!(parse_mac("aabb.ccdd").is_err());
+    assert_eq!(mac.to_string(), "aa:bb:cc:dd:ee:ff");
+}